@@ -0,0 +1,278 @@
+use git2;
+use serde_json;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use pathspec::Pathspec;
+use error::Error;
+
+/// Bumped on every change of the stored data, caches of other versions are discarded.
+pub const VERSION: u32 = 1;
+
+/// How the on-disk cache is used.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheMode {
+    /// Neither read nor written.
+    Disabled,
+    /// Read at the start and updated at the end.
+    Enabled,
+    /// Existing cache is discarded and written anew.
+    Rebuild,
+}
+
+/// Results of the expensive operations: diff stats by commit and blame by file of a revision.
+/// Blame results are kept while the revision moves forward, only files changed on the way are blamed again.
+/// The whole cache is kept in memory, it is read from and written to `.git/gitostat/cache.json`.
+pub struct Cache {
+    path: Option<PathBuf>,
+    data: Data,
+    dirty: bool,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Data {
+    version: u32,
+    /// Insertions and deletions, keyed by `stat_key`.
+    stats: HashMap<String, (usize, usize)>,
+    /// Revision which was blamed, blame results are valid for its files.
+    head: String,
+    /// Lines by the commit which introduced them, keyed by `blame_key`.
+    blame: HashMap<String, HashMap<String, usize>>,
+}
+
+impl Cache {
+    /// Cache which lives only in memory.
+    pub fn new() -> Cache {
+        Cache {
+            path: None,
+            data: Data { version: VERSION, ..Data::default() },
+            dirty: false,
+        }
+    }
+
+    /// Opens the cache of the repository. A broken cache or one of another version is discarded.
+    pub fn open(repo: &git2::Repository, mode: CacheMode) -> Result<Cache, Error> {
+        let path = repo.path().join("gitostat").join("cache.json");
+        let mut cache = Cache::new();
+
+        match mode {
+            CacheMode::Disabled => return Ok(cache),
+            CacheMode::Enabled => {
+                if let Some(data) = read(&path)? {
+                    cache.data = data;
+                }
+            },
+            CacheMode::Rebuild => cache.dirty = true,
+        }
+
+        cache.path = Some(path);
+        Ok(cache)
+    }
+
+    /// Writes the cache back if it was changed. Failure, e.g. in a read-only repository, is only reported.
+    pub fn save(&self) {
+        let path = match self.path {
+            Some(ref path) if self.dirty => path,
+            _ => return,
+        };
+
+        if let Err(err) = write(path, &self.data) {
+            error!("not saving cache {}: {}", path.display(), err);
+        }
+    }
+
+    /// Insertions and deletions of the commit.
+    pub fn stat(&self, commit: git2::Oid, pathspec: Option<&Pathspec>) -> Option<(usize, usize)> {
+        self.data.stats.get(&stat_key(commit, pathspec)).cloned()
+    }
+
+    pub fn insert_stat(&mut self, commit: git2::Oid, pathspec: Option<&Pathspec>, changes: (usize, usize)) {
+        self.data.stats.insert(stat_key(commit, pathspec), changes);
+        self.dirty = true;
+    }
+
+    /// Switches blame results to another revision. If it descends from the previous one, results are dropped
+    /// only for the files changed by the commits between them, otherwise all of them are dropped.
+    pub fn set_head(&mut self, repo: &git2::Repository, head: git2::Oid) -> Result<(), Error> {
+        if self.data.head == head.to_string() {
+            return Ok(());
+        }
+
+        let previous = git2::Oid::from_str(&self.data.head).ok()
+            .and_then(|previous| match repo.graph_descendant_of(head, previous) {
+                Ok(true) => Some(previous),
+                _ => None,
+            });
+        match previous {
+            Some(previous) => {
+                let changed = changed_paths(repo, previous, head)?;
+                self.data.blame.retain(|key, _| !changed.contains(blame_path(key)));
+            },
+            None => self.data.blame.clear(),
+        }
+
+        self.data.head = head.to_string();
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Lines of the file in the current revision by the commit which introduced them.
+    pub fn blame(&self, blob: git2::Oid, path: &Path) -> Option<&HashMap<String, usize>> {
+        self.data.blame.get(&blame_key(blob, path))
+    }
+
+    pub fn insert_blame(&mut self, blob: git2::Oid, path: &Path, lines: HashMap<String, usize>) {
+        self.data.blame.insert(blame_key(blob, path), lines);
+        self.dirty = true;
+    }
+}
+
+/// Stats depend on the pathspec, so it is a part of the key.
+fn stat_key(commit: git2::Oid, pathspec: Option<&Pathspec>) -> String {
+    match pathspec {
+        None => commit.to_string(),
+        Some(pathspec) => format!("{} {}", commit, pathspec.specs().join("\0")),
+    }
+}
+
+fn blame_key(blob: git2::Oid, path: &Path) -> String {
+    format!("{} {}", blob, path.display())
+}
+
+/// Path part of `blame_key`, as it is displayed.
+fn blame_path(key: &str) -> &str {
+    key.splitn(2, ' ').nth(1).unwrap_or("")
+}
+
+/// Paths, as they are displayed, changed by the commits reachable from `head` but not from `previous`.
+/// Merges are diffed against every parent, so files changed on any side are included.
+fn changed_paths(repo: &git2::Repository, previous: git2::Oid, head: git2::Oid) -> Result<HashSet<String>, Error> {
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head)?;
+    revwalk.hide(previous)?;
+
+    let mut paths = HashSet::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let tree = commit.tree()?;
+        for parent in commit.parents() {
+            let diff = repo.diff_tree_to_tree(Some(&parent.tree()?), Some(&tree), None)?;
+            for delta in diff.deltas() {
+                for file in &[delta.old_file(), delta.new_file()] {
+                    if let Some(path) = file.path() {
+                        paths.insert(path.display().to_string());
+                    }
+                }
+            }
+        }
+    }
+    Ok(paths)
+}
+
+fn write(path: &Path, data: &Data) -> Result<(), Error> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // written aside and renamed, so an interrupted run doesn't leave a broken cache
+    let tmp = path.with_extension("tmp");
+    File::create(&tmp)?.write_all(serde_json::to_string(data)?.as_bytes())?;
+    fs::rename(&tmp, path)?;
+
+    Ok(())
+}
+
+/// Returns `None` if there is no cache yet or it can't be used.
+fn read(path: &Path) -> Result<Option<Data>, Error> {
+    let mut content = String::new();
+    match File::open(path) {
+        Ok(mut file) => { file.read_to_string(&mut content)?; },
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    }
+
+    match serde_json::from_str::<Data>(&content) {
+        Ok(ref data) if data.version != VERSION => Ok(None),
+        Ok(data) => Ok(Some(data)),
+        Err(err) => {
+            error!("discarding broken cache {}: {}", path.display(), err);
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::Path;
+    use cache::{Cache, CacheMode};
+
+    #[test]
+    fn smoke() {
+        let (_td, repo) = ::test::repo_init();
+        let id = repo.refname_to_id("HEAD").unwrap();
+
+        let mut cache = Cache::open(&repo, CacheMode::Enabled).unwrap();
+        assert_eq!(cache.stat(id, None), None);
+        cache.insert_stat(id, None, (1, 2));
+        cache.set_head(&repo, id).unwrap();
+        let lines: HashMap<String, usize> = vec![(id.to_string(), 3)].into_iter().collect();
+        cache.insert_blame(id, Path::new("a"), lines.clone());
+        cache.save();
+
+        let mut cache = Cache::open(&repo, CacheMode::Enabled).unwrap();
+        assert_eq!(cache.stat(id, None), Some((1, 2)));
+        assert_eq!(cache.blame(id, Path::new("a")), Some(&lines));
+        // results of the files which weren't changed since are kept
+        let other = ::test::commit(&repo, "b", "b");
+        cache.set_head(&repo, other).unwrap();
+        assert_eq!(cache.blame(id, Path::new("a")), Some(&lines));
+        // and of the changed ones are dropped
+        cache.set_head(&repo, ::test::commit(&repo, "a", "a")).unwrap();
+        assert_eq!(cache.blame(id, Path::new("a")), None);
+        // as all of them are for a revision which doesn't descend from the previous one
+        cache.insert_blame(id, Path::new("a"), lines.clone());
+        cache.set_head(&repo, id).unwrap();
+        assert_eq!(cache.blame(id, Path::new("a")), None);
+
+        assert_eq!(Cache::open(&repo, CacheMode::Rebuild).unwrap().stat(id, None), None);
+        assert_eq!(Cache::open(&repo, CacheMode::Disabled).unwrap().stat(id, None), None);
+
+        // broken or outdated caches are discarded
+        let path = repo.path().join("gitostat/cache.json");
+        File::create(&path).unwrap().write_all(b"{\"version\": 0}").unwrap();
+        assert_eq!(Cache::open(&repo, CacheMode::Enabled).unwrap().stat(id, None), None);
+        File::create(&path).unwrap().write_all(b"garbage").unwrap();
+        assert_eq!(Cache::open(&repo, CacheMode::Enabled).unwrap().stat(id, None), None);
+    }
+
+    #[test]
+    fn read_only() {
+        let (_td, repo) = ::test::repo_init();
+        let id = repo.refname_to_id("HEAD").unwrap();
+        let dir = repo.path().join("gitostat");
+        fs::create_dir(&dir).unwrap();
+        let mut permissions = fs::metadata(&dir).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&dir, permissions.clone()).unwrap();
+
+        // the cache can't be written, which isn't an error
+        let mut cache = Cache::open(&repo, CacheMode::Enabled).unwrap();
+        cache.insert_stat(id, None, (1, 2));
+        cache.save();
+
+        // neither is a file in place of the directory, which can't be written even by root
+        permissions.set_readonly(false);
+        fs::set_permissions(&dir, permissions).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        File::create(&dir).unwrap();
+        let mut cache = Cache::open(&repo, CacheMode::Rebuild).unwrap();
+        cache.insert_stat(id, None, (1, 2));
+        cache.save();
+        assert!(dir.is_file());
+    }
+}
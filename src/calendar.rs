@@ -0,0 +1,184 @@
+use std::{cmp, fmt};
+use std::collections::BTreeMap;
+use chrono::{Datelike, Duration, NaiveDate};
+use git2;
+
+use heatmap::Timezone;
+use style::Style;
+
+const DAYS: [&'static str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+const MONTHS: [&'static str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Commits by the day, shown as a grid of a column per week and a row per day of week for each year.
+pub struct Calendar {
+    days: BTreeMap<NaiveDate, u32>,
+    timezone: Timezone,
+    style: Style,
+}
+
+/// Calendar of the single year.
+pub struct Year<'a> {
+    calendar: &'a Calendar,
+    year: i32,
+}
+
+impl Calendar {
+    pub fn new() -> Calendar {
+        Calendar::with_timezone(Timezone::Author)
+    }
+
+    pub fn with_timezone(timezone: Timezone) -> Calendar {
+        Calendar { days: BTreeMap::new(), timezone: timezone, style: Style::plain() }
+    }
+
+    pub fn set_style(&mut self, style: Style) -> &mut Self {
+        self.style = style;
+        self
+    }
+
+    pub fn append(&mut self, time: &git2::Time) {
+        let date = self.timezone.convert(time).naive_local().date();
+        *self.days.entry(date).or_insert(0) += 1;
+    }
+
+    /// Number of commits at the given date.
+    pub fn get(&self, date: NaiveDate) -> u32 {
+        self.days.get(&date).cloned().unwrap_or(0)
+    }
+
+    /// Every year from the first commit to the last one.
+    pub fn years(&self) -> Vec<Year> {
+        match (self.days.keys().next(), self.days.keys().last()) {
+            (Some(first), Some(last)) => (first.year()..last.year() + 1)
+                .map(|year| Year { calendar: self, year: year })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl<'a> Year<'a> {
+    pub fn year(&self) -> i32 {
+        self.year
+    }
+
+    /// Columns of the grid, Monday first. Days of the neighbour years in the first
+    /// and the last weeks are `None`. Usually there are 53 weeks.
+    pub fn weeks(&self) -> Vec<[Option<u32>; 7]> {
+        let first = NaiveDate::from_ymd_opt(self.year, 1, 1).unwrap();
+        let offset = first.weekday().num_days_from_monday() as usize;
+
+        let mut weeks = Vec::new();
+        let mut date = first;
+        while date.year() == self.year {
+            let index = (date.ordinal0() as usize + offset) / 7;
+            if weeks.len() <= index {
+                weeks.push([None; 7]);
+            }
+            weeks[index][date.weekday().num_days_from_monday() as usize] = Some(self.calendar.get(date));
+            date = date.succ_opt().unwrap();
+        }
+
+        weeks
+    }
+
+    /// Date of the cell in the grid, it may belong to the neighbour year.
+    pub fn date(&self, week: usize, day: usize) -> NaiveDate {
+        let first = NaiveDate::from_ymd_opt(self.year, 1, 1).unwrap();
+        let offset = first.weekday().num_days_from_monday() as i64;
+        first + Duration::days((week * 7 + day) as i64 - offset)
+    }
+
+    /// Month of each week, by its first day in the year.
+    fn months(&self) -> Vec<u32> {
+        (0..self.weeks().len()).map(|week| {
+            let monday = self.date(week, 0);
+            if monday.year() < self.year { 0 } else { monday.month0() }
+        }).collect()
+    }
+}
+
+impl<'a> fmt::Display for Year<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let style = self.calendar.style;
+        let arts = style.shades();
+
+        let weeks = self.weeks();
+        let max = weeks.iter().flat_map(|week| week.iter()).filter_map(|&day| day).max().unwrap_or(0);
+        let max = cmp::max(1, max);
+
+        // names of months above their first weeks, if there is enough space
+        let mut header: Vec<char> = vec![' '; weeks.len() + 3];
+        let mut previous = None;
+        for (i, &month) in self.months().iter().enumerate() {
+            if previous != Some(month) && header[i..].iter().take(4).all(|&c| c == ' ') {
+                for (j, c) in MONTHS[month as usize].chars().enumerate() {
+                    header[i + j] = c;
+                }
+            }
+            previous = Some(month);
+        }
+        writeln!(f, "{}", self.year)?;
+        writeln!(f, "    {}", header.into_iter().collect::<String>().trim_end())?;
+
+        for day in 0..7 {
+            // the last week of the year may lack the day, nothing is drawn after it
+            let len = weeks.iter().rposition(|week| week[day].is_some()).map_or(0, |i| i + 1);
+            let cells: String = weeks[..len].iter().map(|week| match week[day] {
+                Some(value) => {
+                    let level = (value as f32 / max as f32 * (arts.len() - 1) as f32).ceil() as usize;
+                    style.paint(arts[level], level)
+                },
+                None => " ".to_string(),
+            }).collect();
+            writeln!(f, "{} {}", DAYS[day], cells)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Calendar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Days in {}:", self.timezone)?;
+        for year in self.years() {
+            writeln!(f, "{}", year)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use git2;
+    use calendar::Calendar;
+
+    #[test]
+    fn smoke() {
+        let mut calendar = Calendar::new();
+        // Sun, 28 Jun 2015 13:17:20 +0600
+        calendar.append(&git2::Time::new(1435475840, 6*60));
+        calendar.append(&git2::Time::new(1435475840, 6*60));
+        // Thu, 1 Jan 2015 12:00:00 +0000
+        calendar.append(&git2::Time::new(1420113600, 0));
+        assert_eq!(calendar.get(NaiveDate::from_ymd(2015, 6, 28)), 2);
+
+        let years = calendar.years();
+        assert_eq!(years.len(), 1);
+        let weeks = years[0].weeks();
+        assert_eq!(weeks.len(), 53);
+        // the year starts on Thursday
+        assert_eq!(weeks[0], [None, None, None, Some(1), Some(0), Some(0), Some(0)]);
+        assert_eq!(weeks[25][6], Some(2));
+        assert_eq!(years[0].date(25, 6), NaiveDate::from_ymd(2015, 6, 28));
+
+        let text = format!("{}", years[0]);
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "2015");
+        assert!(lines[1].starts_with("    Jan  Feb"));
+        assert!(lines[5].starts_with("Thu ▒...."));
+        assert_eq!(lines[8].chars().nth(4 + 25), Some('█'));
+    }
+}
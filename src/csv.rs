@@ -0,0 +1,131 @@
+use std::collections::BTreeMap;
+
+use filetype::{self, Breakdown};
+use heatmap::Heatmap;
+use personal::PersonalStats;
+use report::Report;
+
+const DAYS: [&'static str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Renders every report as a separate CSV table, returns pairs of the table name and its content.
+pub fn render(report: &Report) -> Vec<(&'static str, String)> {
+    vec![
+        ("heatmap", render_heatmap(&report.heatmap)),
+        ("authors", render_authors(&report.authors)),
+        ("weekly_activity", render_weeks("commits", report.authors.weeks().into_iter())),
+        ("files", render_weeks("files", report.files.iter().map(|(key, &val)| (key.clone(), val)))),
+        ("file_types", render_file_types(&report.file_types)),
+        ("lines", render_lines(&report.lines)),
+    ]
+}
+
+fn render_heatmap(heatmap: &Heatmap) -> String {
+    let mut header = vec!["day".to_string()];
+    header.extend((0..24).map(|hour| hour.to_string()));
+
+    let mut result = line(&header);
+    for (day, name) in DAYS.iter().enumerate() {
+        let mut fields = vec![name.to_string()];
+        fields.extend((0..24).map(|hour| heatmap.get(day, hour).to_string()));
+        result.push_str(&line(&fields));
+    }
+
+    result
+}
+
+fn render_authors(authors: &PersonalStats) -> String {
+    let header = ["author", "commits", "commits_percent", "insertions", "deletions", "owned_lines",
+                  "owned_lines_percent", "live_code_percent", "age_in_days", "active_days", "active_days_percent"];
+
+    let mut result = line(&header.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+    for row in authors.rows() {
+        result.push_str(&line(&[
+            row.name.clone(),
+            row.commits.to_string(),
+            format!("{:.2}", row.commits_percent),
+            row.insertions.to_string(),
+            row.deletions.to_string(),
+            row.owned_lines.to_string(),
+            format!("{:.2}", row.owned_lines_percent),
+            format!("{:.2}", row.live_code_percent),
+            row.age_in_days.to_string(),
+            row.active_days.to_string(),
+            format!("{:.2}", row.active_days_percent),
+        ]));
+    }
+
+    result
+}
+
+fn render_weeks<I: Iterator<Item=(String, usize)>>(name: &str, iter: I) -> String {
+    let mut result = line(&["week".to_string(), name.to_string()]);
+    for (week, value) in iter {
+        result.push_str(&line(&[week, value.to_string()]));
+    }
+
+    result
+}
+
+fn render_file_types(series: &BTreeMap<String, Breakdown>) -> String {
+    let mut result = line(&["week".to_string(), "type".to_string(), "files".to_string(), "percent".to_string()]);
+    for (week, types) in series {
+        let total: usize = types.values().sum();
+        for (name, num) in filetype::ranked(types) {
+            result.push_str(&line(&[
+                week.clone(),
+                name.to_string(),
+                num.to_string(),
+                format!("{:.2}", num as f32 * 100. / total as f32),
+            ]));
+        }
+    }
+
+    result
+}
+
+fn render_lines(series: &BTreeMap<String, Breakdown>) -> String {
+    let mut result = line(&["week".to_string(), "type".to_string(), "lines".to_string()]);
+    for (week, types) in series {
+        for (name, num) in filetype::ranked(types) {
+            result.push_str(&line(&[week.clone(), name.to_string(), num.to_string()]));
+        }
+    }
+
+    result
+}
+
+fn line(fields: &[String]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| escape(field)).collect();
+    fields.join(",") + "\n"
+}
+
+/// Quotes field if it contains separator, quotes or line breaks.
+fn escape(field: &str) -> String {
+    if field.contains(|c: char| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git2;
+    use heatmap::Heatmap;
+    use csv::{escape, render_heatmap};
+
+    #[test]
+    fn smoke() {
+        assert_eq!(escape("name <email>"), "name <email>");
+        assert_eq!(escape("Doe, \"John\""), "\"Doe, \"\"John\"\"\"");
+
+        let mut hm = Heatmap::new();
+        // Sun, 28 Jun 2015 13:17:20 +0600
+        hm.append(&git2::Time::new(1435475840, 6*60));
+        let csv = render_heatmap(&hm);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 8);
+        assert!(lines[0].starts_with("day,0,1,2,"));
+        assert_eq!(lines[7], "Sun,0,0,0,0,0,0,0,0,0,0,0,0,0,1,0,0,0,0,0,0,0,0,0,0");
+    }
+}
@@ -0,0 +1,155 @@
+use git2;
+use chrono::{self, Datelike, DateTime, NaiveDate, NaiveDateTime, NaiveTime};
+use chrono::offset::{FixedOffset, Local, Offset, TimeZone, Utc};
+
+/// Time window to which the walked commits are limited.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Window {
+    pub since: Option<DateTime<FixedOffset>>,
+    pub until: Option<DateTime<FixedOffset>>,
+}
+
+impl Window {
+    pub fn new(since: Option<DateTime<FixedOffset>>, until: Option<DateTime<FixedOffset>>) -> Window {
+        Window { since: since, until: until }
+    }
+
+    pub fn contains(&self, time: &git2::Time) -> bool {
+        let seconds = time.seconds();
+
+        self.since.map_or(true, |since| since.timestamp() <= seconds) &&
+            self.until.map_or(true, |until| seconds <= until.timestamp())
+    }
+}
+
+/// Parses date in one of the forms understood by `git log --since`:
+/// `2015-06-28`, `2015-06-28 13:17:20`, RFC 3339, `@<timestamp>`,
+/// `now`, `today`, `yesterday` and relative ones like `3 months ago` or `2.weeks.ago`.
+pub fn parse(input: &str) -> Option<DateTime<FixedOffset>> {
+    let now = Local::now();
+    parse_relative_to(input, now.with_timezone(&now.offset().fix()))
+}
+
+fn parse_relative_to(input: &str, now: DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    let input = input.trim();
+    let tz = *now.offset();
+    let midnight = NaiveTime::from_hms_opt(0, 0, 0).unwrap();
+
+    match input {
+        "now" => return Some(now),
+        "today" => return tz.from_local_datetime(&now.naive_local().date().and_time(midnight)).single(),
+        "yesterday" => {
+            let date = now.naive_local().date() - chrono::Duration::days(1);
+            return tz.from_local_datetime(&date.and_time(midnight)).single();
+        },
+        _ => {}
+    }
+
+    if input.starts_with('@') {
+        return input[1..].parse::<i64>().ok()
+            .and_then(|timestamp| Utc.timestamp_opt(timestamp, 0).single())
+            .map(|datetime| datetime.with_timezone(&tz));
+    }
+
+    if input.ends_with("ago") {
+        return parse_ago(&input.replace('.', " "), now);
+    }
+
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(input) {
+        return Some(datetime);
+    }
+    if let Ok(datetime) = DateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S %z") {
+        return Some(datetime);
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S") {
+        return tz.from_local_datetime(&datetime).single();
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(input, "%Y-%m-%d") {
+        return tz.from_local_datetime(&date.and_time(midnight)).single();
+    }
+
+    None
+}
+
+/// Parses `<number> <unit> ago`, `None` if the date is out of range.
+fn parse_ago(input: &str, now: DateTime<FixedOffset>) -> Option<DateTime<FixedOffset>> {
+    let words: Vec<&str> = input.split_whitespace().collect();
+    if words.len() != 3 || words[2] != "ago" {
+        return None;
+    }
+
+    let number = match words[0].parse::<i64>() {
+        Ok(number) => number,
+        Err(_) => return None
+    };
+    let unit = words[1].trim_end_matches('s');
+
+    let seconds: i64 = match unit {
+        "second" | "sec" => 1,
+        "minute" | "min" => 60,
+        "hour" => 60 * 60,
+        "day" => 24 * 60 * 60,
+        "week" => 7 * 24 * 60 * 60,
+        "month" => return sub_months(now, number),
+        "year" => return number.checked_mul(12).and_then(|months| sub_months(now, months)),
+        _ => return None
+    };
+
+    // `Duration::seconds` and friends panic on overflow, milliseconds don't
+    number.checked_mul(seconds * 1000)
+        .and_then(|milliseconds| now.checked_sub_signed(chrono::Duration::milliseconds(milliseconds)))
+}
+
+/// Moves date back by calendar months, the day is clamped to the length of the target month.
+fn sub_months(now: DateTime<FixedOffset>, months: i64) -> Option<DateTime<FixedOffset>> {
+    let total = match (now.year() as i64 * 12 + now.month0() as i64).checked_sub(months) {
+        Some(total) => total,
+        None => return None
+    };
+    // rounded down rather than towards zero, so that months before year 0 are right
+    let month0 = ((total % 12) + 12) % 12;
+    let year = (total - month0) / 12;
+    if year < i32::min_value() as i64 || year > i32::max_value() as i64 {
+        return None;
+    }
+    let (year, month) = (year as i32, month0 as u32 + 1);
+
+    let mut day = now.day();
+    loop {
+        if let Some(date) = NaiveDate::from_ymd_opt(year, month, day) {
+            return now.offset().from_local_datetime(&date.and_time(now.time())).single();
+        }
+        if day == 1 { return None; }
+        day -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use date::parse_relative_to;
+
+    #[test]
+    fn smoke() {
+        let now = DateTime::parse_from_rfc3339("2015-05-31T13:17:20+06:00").unwrap();
+        let parse = |input| parse_relative_to(input, now).map(|date| date.to_rfc3339());
+
+        assert_eq!(parse("2015-01-02"), Some("2015-01-02T00:00:00+06:00".to_string()));
+        assert_eq!(parse("2015-01-02 10:00:00 +0000"), Some("2015-01-02T10:00:00+00:00".to_string()));
+        assert_eq!(parse("yesterday"), Some("2015-05-30T00:00:00+06:00".to_string()));
+        assert_eq!(parse("2 weeks ago"), Some("2015-05-17T13:17:20+06:00".to_string()));
+        assert_eq!(parse("3.months.ago"), Some("2015-02-28T13:17:20+06:00".to_string()));
+        assert_eq!(parse("1 year ago"), Some("2014-05-31T13:17:20+06:00".to_string()));
+        assert_eq!(parse("last tuesday"), None);
+
+        // out of range
+        assert_eq!(parse("99999999999 weeks ago"), None);
+        assert_eq!(parse("9223372036854775807 seconds ago"), None);
+        assert_eq!(parse("-99999999999 days ago"), None);
+        assert_eq!(parse("9223372036854775807 years ago"), None);
+        assert_eq!(parse("99999999999 months ago"), None);
+        assert_eq!(parse("-9223372036854775808 months ago"), None);
+        assert_eq!(parse("@99999999999999999"), None);
+        assert_eq!(parse("24185 months ago"), Some("-0001-12-31T13:17:20+06:00".to_string()));
+    }
+}
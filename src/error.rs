@@ -0,0 +1,73 @@
+use std::{error, fmt, io, result};
+use git2;
+use serde_json;
+
+/// Errors which may occur during the analysis.
+#[derive(Debug)]
+pub enum Error {
+    /// Failure of an underlying git operation.
+    Git(git2::Error),
+    /// Failure of reading inputs or writing reports.
+    Io(io::Error),
+    /// Mailmap source which can't be read, e.g. `mailmap.blob` which isn't a blob. `name` is as in `mailmap::Source`.
+    Mailmap { name: String, message: String },
+    /// Invalid option or configuration value.
+    Config(String),
+    /// Text which is expected to be UTF-8 but isn't.
+    Encoding(String),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl Error {
+    /// Exit code of the process for the error, distinct per category.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            Error::Config(_) => 2,
+            Error::Git(_) => 3,
+            Error::Io(_) => 4,
+            Error::Mailmap { .. } => 5,
+            Error::Encoding(_) => 6,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Git(ref err) => write!(f, "git: {}", err),
+            Error::Io(ref err) => write!(f, "io: {}", err),
+            Error::Mailmap { ref name, ref message } => write!(f, "mailmap: {}: {}", name, message),
+            Error::Config(ref message) => write!(f, "config: {}", message),
+            Error::Encoding(ref message) => write!(f, "encoding: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Error::Git(ref err) => Some(err),
+            Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<git2::Error> for Error {
+    fn from(err: git2::Error) -> Error {
+        Error::Git(err)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Error {
+        Error::Io(err.into())
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Name of the type used for files which aren't recognized.
+pub const OTHER: &'static str = "Other";
+
+/// Number of files of each type, keyed by the type name.
+pub type Breakdown = BTreeMap<&'static str, usize>;
+
+/// Guesses the type (mostly language) of the file by its name and extension.
+pub fn classify(path: &Path) -> &'static str {
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or("");
+
+    match name {
+        "Makefile" | "makefile" | "GNUmakefile" => return "Makefile",
+        "Dockerfile" => return "Dockerfile",
+        "CMakeLists.txt" => return "CMake",
+        "Cargo.lock" => return "TOML",
+        _ => {}
+    }
+
+    let extension = match path.extension().and_then(|ext| ext.to_str()) {
+        Some(extension) => extension.to_lowercase(),
+        None => return OTHER,
+    };
+
+    match extension.as_str() {
+        "rs" => "Rust",
+        "c" | "h" => "C",
+        "cc" | "cpp" | "cxx" | "hh" | "hpp" | "hxx" => "C++",
+        "cs" => "C#",
+        "go" => "Go",
+        "java" => "Java",
+        "kt" | "kts" => "Kotlin",
+        "scala" => "Scala",
+        "swift" => "Swift",
+        "m" | "mm" => "Objective-C",
+        "py" => "Python",
+        "rb" => "Ruby",
+        "php" => "PHP",
+        "pl" | "pm" => "Perl",
+        "lua" => "Lua",
+        "hs" => "Haskell",
+        "ml" | "mli" => "OCaml",
+        "ex" | "exs" => "Elixir",
+        "erl" | "hrl" => "Erlang",
+        "clj" | "cljs" => "Clojure",
+        "js" | "mjs" | "jsx" => "JavaScript",
+        "ts" | "tsx" => "TypeScript",
+        "sh" | "bash" | "zsh" => "Shell",
+        "sql" => "SQL",
+        "html" | "htm" => "HTML",
+        "css" | "scss" | "sass" | "less" => "CSS",
+        "md" | "markdown" => "Markdown",
+        "rst" => "reStructuredText",
+        "txt" => "Text",
+        "toml" => "TOML",
+        "yml" | "yaml" => "YAML",
+        "json" => "JSON",
+        "xml" => "XML",
+        "ini" | "cfg" | "conf" => "Config",
+        "png" | "jpg" | "jpeg" | "gif" | "svg" | "ico" => "Image",
+        _ => OTHER,
+    }
+}
+
+/// Counts files of each type.
+pub fn breakdown<'a, I: Iterator<Item=&'a Path>>(paths: I) -> Breakdown {
+    let mut result = BTreeMap::new();
+    for path in paths {
+        *result.entry(classify(path)).or_insert(0) += 1;
+    }
+    result
+}
+
+/// Types sorted by the share in the breakdown, the largest first, `Other` is always the last.
+pub fn ranked(breakdown: &Breakdown) -> Vec<(&'static str, usize)> {
+    let mut result: Vec<(&'static str, usize)> = breakdown.iter().map(|(&name, &num)| (name, num)).collect();
+    result.sort_by(|a, b| (a.0 == OTHER).cmp(&(b.0 == OTHER)).then(b.1.cmp(&a.1)).then(a.0.cmp(b.0)));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use filetype::{breakdown, classify, ranked, OTHER};
+
+    #[test]
+    fn smoke() {
+        assert_eq!(classify(Path::new("src/main.rs")), "Rust");
+        assert_eq!(classify(Path::new("Cargo.toml")), "TOML");
+        assert_eq!(classify(Path::new("docs/README.MD")), "Markdown");
+        assert_eq!(classify(Path::new("Makefile")), "Makefile");
+        assert_eq!(classify(Path::new("LICENSE")), OTHER);
+
+        let paths = ["a.rs", "b.rs", "LICENSE", "README.md", "c.rs", "d.toml", "e.toml", "f.toml", "g"];
+        let types = breakdown(paths.iter().map(Path::new));
+        assert_eq!(ranked(&types), vec![("Rust", 3), ("TOML", 3), ("Markdown", 1), (OTHER, 2)]);
+    }
+}
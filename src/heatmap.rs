@@ -1,21 +1,107 @@
 use std::{fmt, cmp};
-use chrono::offset::{FixedOffset, Utc, TimeZone};
-use chrono::{Datelike, Timelike};
+use chrono::offset::{FixedOffset, Local, Utc, TimeZone};
+use chrono::{DateTime, Datelike, Timelike};
 use git2;
 
+use style::Style;
+
+const DAYS: [&'static str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Timezone in which commits are bucketed by hours.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Timezone {
+    /// Offset recorded in each commit, i.e. local working hours of its author.
+    Author,
+    Utc,
+    /// Timezone of this machine.
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl Timezone {
+    /// Parses `author`, `utc`, `local` or a fixed offset like `+05:30`, `-0800`, `+03`.
+    /// Named zones like `Europe/Berlin` aren't supported, their offset changes over the year.
+    pub fn parse(input: &str) -> Option<Timezone> {
+        match input.to_lowercase().as_str() {
+            "author" => return Some(Timezone::Author),
+            "utc" => return Some(Timezone::Utc),
+            "local" => return Some(Timezone::Local),
+            _ => {}
+        }
+
+        let sign = match input.chars().next() {
+            Some('+') => 1,
+            Some('-') => -1,
+            _ => return None,
+        };
+        let digits: String = input[1..].chars().filter(|&c| c != ':').collect();
+        if !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            return None;
+        }
+        let (hours, minutes) = match digits.len() {
+            2 => (&digits[..], "0"),
+            4 => (&digits[..2], &digits[2..]),
+            _ => return None,
+        };
+        let (hours, minutes): (i32, i32) = match (hours.parse(), minutes.parse()) {
+            (Ok(hours), Ok(minutes)) if hours <= 14 && minutes < 60 => (hours, minutes),
+            _ => return None,
+        };
+
+        FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60)).map(Timezone::Fixed)
+    }
+
+    /// Date and time of the commit in this timezone.
+    pub fn convert(&self, time: &git2::Time) -> DateTime<FixedOffset> {
+        let utc = Utc.timestamp_opt(time.seconds(), 0).unwrap();
+        let offset = match *self {
+            Timezone::Author => FixedOffset::east_opt(time.offset_minutes() * 60).unwrap(),
+            Timezone::Utc => FixedOffset::east_opt(0).unwrap(),
+            Timezone::Local => *utc.with_timezone(&Local).offset(),
+            Timezone::Fixed(offset) => offset,
+        };
+        utc.with_timezone(&offset)
+    }
+}
+
+impl fmt::Display for Timezone {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Timezone::Author => write!(f, "author's time"),
+            Timezone::Utc => write!(f, "UTC"),
+            Timezone::Local => write!(f, "local time"),
+            Timezone::Fixed(offset) => write!(f, "UTC{}", offset),
+        }
+    }
+}
+
 pub struct Heatmap {
-    array: [u32; 24*7]
+    array: [u32; 24*7],
+    timezone: Timezone,
+    style: Style,
 }
 
 impl Heatmap {
     pub fn new() -> Heatmap {
-        Heatmap { array: [0u32; 24*7] }
+        Heatmap::with_timezone(Timezone::Author)
+    }
+
+    pub fn with_timezone(timezone: Timezone) -> Heatmap {
+        Heatmap { array: [0u32; 24*7], timezone: timezone, style: Style::plain() }
+    }
+
+    pub fn timezone(&self) -> Timezone {
+        self.timezone
+    }
+
+    pub fn set_style(&mut self, style: Style) -> &mut Self {
+        self.style = style;
+        self
     }
 
     pub fn append(&mut self, time: &git2::Time) {
 
-        let timestamp = Utc.timestamp(time.seconds(), 0)
-            .with_timezone(&FixedOffset::east(time.offset_minutes() * 60));
+        let timestamp = self.timezone.convert(time);
 
         let day = timestamp.weekday().num_days_from_monday();
         let hour = timestamp.hour();
@@ -23,17 +109,24 @@ impl Heatmap {
         self.array[(day * 24 + hour) as usize] += 1;
     }
 
+    /// Number of commits at the given day of week (0 is Monday) and hour.
+    pub fn get(&self, day: usize, hour: usize) -> u32 {
+        self.array[day * 24 + hour]
+    }
+
+    fn max(&self) -> u32 {
+        cmp::max(1, self.array.iter().cloned().max().unwrap_or(0))
+    }
+
 }
 
 impl fmt::Display for Heatmap {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut vec = self.array.to_vec();
-        vec.sort_by(|a, b| b.cmp(a));
-        let max = cmp::max(1, vec[0]);
+        let max = self.max();
 
-        const ARTS: [char; 5] = ['.', '▪', '◾', '◼', '⬛'];
-        const DAYS: [&'static str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+        let arts = self.style.cells();
 
+        writeln!(f, "Hours in {}:", self.timezone)?;
         write!(f, "     ")?;
         for i in 0..24 {
             write!(f, "{:3}", i)?;
@@ -43,7 +136,8 @@ impl fmt::Display for Heatmap {
         for day in 0..7 {
             write!(f, "{}: ", DAYS[day])?;
             for hour in 0..24 {
-                write!(f, "{: >3}", ARTS[(self.array[day * 24 + hour] as f32 / max as f32 * (ARTS.len() - 1) as f32) as usize])?;
+                let level = (self.array[day * 24 + hour] as f32 / max as f32 * (arts.len() - 1) as f32) as usize;
+                write!(f, "  {}", self.style.paint(arts[level], level))?;
             }
             write!(f, "\n")?;
         }
@@ -52,10 +146,58 @@ impl fmt::Display for Heatmap {
     }
 }
 
+/// Heatmaps of several authors next to each other, a column per hour, each scaled on its own.
+pub struct SideBySide<'a> {
+    pub heatmaps: Vec<(String, &'a Heatmap)>,
+    /// Number of heatmaps in a row.
+    pub columns: usize,
+    pub style: Style,
+}
+
+impl<'a> fmt::Display for SideBySide<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let arts = self.style.shades();
+        const WIDTH: usize = 4 + 24;
+        const GAP: &'static str = "   ";
+
+        if let Some(&(_, heatmap)) = self.heatmaps.first() {
+            writeln!(f, "Hours in {}:", heatmap.timezone)?;
+        }
+
+        for row in self.heatmaps.chunks(cmp::max(1, self.columns)) {
+            let titles: Vec<String> = row.iter().map(|&(ref name, _)| {
+                let name: String = name.chars().take(WIDTH).collect();
+                format!("{:width$}", name, width = WIDTH)
+            }).collect();
+            writeln!(f, "{}", titles.join(GAP).trim_end())?;
+
+            let header = vec!["    0     6     12    18    "; row.len()];
+            writeln!(f, "{}", header.join(GAP).trim_end())?;
+
+            for day in 0..7 {
+                let lines: Vec<String> = row.iter().map(|&(_, heatmap)| {
+                    let max = heatmap.max();
+                    let cells: String = (0..24).map(|hour| {
+                        let level = (heatmap.get(day, hour) as f32 / max as f32 * (arts.len() - 1) as f32) as usize;
+                        self.style.paint(arts[level], level)
+                    }).collect();
+                    format!("{} {}", DAYS[day], cells)
+                }).collect();
+                writeln!(f, "{}", lines.join(GAP))?;
+            }
+            writeln!(f, "")?;
+        }
+
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use git2;
-    use heatmap::Heatmap;
+    use chrono::FixedOffset;
+    use heatmap::{Heatmap, SideBySide, Timezone};
+    use style::Style;
 
     #[test]
     fn smoke() {
@@ -64,4 +206,40 @@ mod tests {
         hm.append(&git2::Time::new(1435475840, 6*60));
         assert_eq!(hm.array[6 * 24 + 13], 1);
     }
+
+    #[test]
+    fn timezone() {
+        assert_eq!(Timezone::parse("UTC"), Some(Timezone::Utc));
+        assert_eq!(Timezone::parse("+05:30"), Some(Timezone::Fixed(FixedOffset::east(5 * 3600 + 30 * 60))));
+        assert_eq!(Timezone::parse("-0800"), Some(Timezone::Fixed(FixedOffset::west(8 * 3600))));
+        assert_eq!(Timezone::parse("+3"), None);
+        assert_eq!(Timezone::parse("+1é1"), None);
+        assert_eq!(Timezone::parse("+é"), None);
+        assert_eq!(Timezone::parse("Europe/Berlin"), None);
+        assert_eq!(format!("{}", Timezone::parse("-08").unwrap()), "UTC-08:00");
+
+        // Sun, 28 Jun 2015 13:17:20 +0600
+        let time = git2::Time::new(1435475840, 6*60);
+        let mut utc = Heatmap::with_timezone(Timezone::Utc);
+        utc.append(&time);
+        assert_eq!(utc.get(6, 7), 1);
+        let mut fixed = Heatmap::with_timezone(Timezone::parse("-08:00").unwrap());
+        fixed.append(&time);
+        assert_eq!(fixed.get(5, 23), 1);
+        assert!(format!("{}", fixed).starts_with("Hours in UTC-08:00:\n"));
+    }
+
+    #[test]
+    fn side_by_side() {
+        let mut alice = Heatmap::new();
+        // Sun, 28 Jun 2015 13:17:20 +0600
+        alice.append(&git2::Time::new(1435475840, 6*60));
+        let bob = Heatmap::new();
+
+        let text = format!("{}", SideBySide { heatmaps: vec![("Alice".to_string(), &alice), ("Bob".to_string(), &bob)], columns: 2, style: Style::plain() });
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Hours in author's time:");
+        assert_eq!(lines[1], format!("{:28}   Bob", "Alice"));
+        assert_eq!(lines[9], format!("Sun {}█{}   Sun {}", ".".repeat(13), ".".repeat(10), ".".repeat(24)));
+    }
 }
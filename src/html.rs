@@ -0,0 +1,280 @@
+use std::cmp;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use calendar::Calendar;
+use filetype::{self, Breakdown};
+use heatmap::Heatmap;
+use personal::PersonalStats;
+use report::Report;
+
+const DAYS: [&'static str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+const STYLE: &'static str = "
+body { font-family: sans-serif; margin: 2em; color: #24292e; }
+h2 { margin-top: 2em; }
+table { border-collapse: collapse; }
+td, th { padding: 4px 8px; border: 1px solid #e1e4e8; text-align: right; }
+th { background: #f6f8fa; cursor: pointer; user-select: none; }
+td:first-child, th:first-child { text-align: left; }
+table.heatmap td { width: 1.5em; height: 1.5em; padding: 0; border: 2px solid #fff; }
+table.heatmap th { background: none; cursor: default; font-weight: normal; font-size: small; }
+table.calendar td { width: 10px; height: 10px; padding: 0; border: 2px solid #fff; }
+table.calendar th { background: none; cursor: default; font-weight: normal; font-size: small; text-align: left; }
+tr.total td { font-weight: bold; }
+svg text { font-size: 10px; fill: #586069; }
+.legend span { display: inline-block; margin-right: 1em; }
+.legend i { display: inline-block; width: 10px; height: 10px; margin-right: 4px; }
+";
+
+/// Clicking on a column header sorts the table by that column, the total row stays at the bottom.
+const SCRIPT: &'static str = "
+document.querySelectorAll('table.sortable').forEach(function (table) {
+  table.querySelectorAll('th').forEach(function (th, column) {
+    th.addEventListener('click', function () {
+      var body = table.tBodies[0];
+      var rows = Array.prototype.slice.call(body.querySelectorAll('tr:not(.total)'));
+      var asc = th.dataset.order !== 'asc';
+      th.dataset.order = asc ? 'asc' : 'desc';
+      rows.sort(function (a, b) {
+        var x = a.cells[column].dataset.value, y = b.cells[column].dataset.value;
+        var cmp = isNaN(x) ? x.localeCompare(y) : x - y;
+        return asc ? cmp : -cmp;
+      });
+      rows.forEach(function (row) { body.insertBefore(row, body.querySelector('tr.total')); });
+    });
+  });
+});
+";
+
+/// Renders self-contained HTML page, all styles, scripts and charts are inlined.
+pub fn render(report: &Report) -> String {
+    let mut html = String::new();
+
+    html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>gitostat</title>\n");
+    html.push_str(&format!("<style>{}</style>\n", STYLE));
+    html.push_str("</head>\n<body>\n");
+
+    write!(html, "<h2>Heatmap of the most active hours of the week ({})</h2>\n", escape(&report.heatmap.timezone().to_string())).unwrap();
+    html.push_str(&render_heatmap(&report.heatmap));
+
+    html.push_str("<h2>Contributions calendar</h2>\n");
+    html.push_str(&render_calendar(&report.calendar));
+
+    html.push_str("<h2>Activity by weeks</h2>\n");
+    html.push_str(&render_bars(&report.authors.weeks(), "#2c974b"));
+
+    html.push_str("<h2>Files in repo</h2>\n");
+    let files: Vec<(String, usize)> = report.files.iter().map(|(key, &val)| (key.clone(), val)).collect();
+    html.push_str(&render_bars(&files, "#0366d6"));
+
+    html.push_str("<h2>File types</h2>\n");
+    html.push_str(&render_stacked(&report.file_types, true));
+
+    html.push_str("<h2>Lines of code</h2>\n");
+    html.push_str(&render_stacked(&report.lines, false));
+
+    html.push_str("<h2>Authors</h2>\n");
+    html.push_str(&render_authors(&report.authors));
+
+    html.push_str(&format!("<script>{}</script>\n", SCRIPT));
+    html.push_str("</body>\n</html>\n");
+
+    html
+}
+
+fn render_heatmap(heatmap: &Heatmap) -> String {
+    let max = (0..7).flat_map(|day| (0..24).map(move |hour| heatmap.get(day, hour))).max().unwrap_or(0);
+    let max = cmp::max(1, max);
+
+    let mut html = String::from("<table class=\"heatmap\">\n<tr><th></th>");
+    for hour in 0..24 {
+        write!(html, "<th>{}</th>", hour).unwrap();
+    }
+    html.push_str("</tr>\n");
+
+    for (day, name) in DAYS.iter().enumerate() {
+        write!(html, "<tr><th>{}</th>", name).unwrap();
+        for hour in 0..24 {
+            let value = heatmap.get(day, hour);
+            let alpha = if value == 0 { 0.05 } else { 0.2 + 0.8 * value as f32 / max as f32 };
+            write!(html, "<td style=\"background: rgba(44, 151, 75, {:.2})\" title=\"{} {}:00 &mdash; {} commits\"></td>",
+                   alpha, name, hour, value).unwrap();
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+
+    html
+}
+
+/// Grid of a column per week and a row per day of week for each year, every day has a tooltip.
+fn render_calendar(calendar: &Calendar) -> String {
+    let mut html = String::new();
+
+    for year in calendar.years() {
+        let weeks = year.weeks();
+        let max = weeks.iter().flat_map(|week| week.iter()).filter_map(|&day| day).max().unwrap_or(0);
+        let max = cmp::max(1, max);
+
+        write!(html, "<table class=\"calendar\">\n<tr><th colspan=\"{}\">{}</th></tr>\n", weeks.len() + 1, year.year()).unwrap();
+        for (day, name) in DAYS.iter().enumerate() {
+            write!(html, "<tr><th>{}</th>", name).unwrap();
+            for (i, week) in weeks.iter().enumerate() {
+                match week[day] {
+                    Some(value) => {
+                        let alpha = if value == 0 { 0.05 } else { 0.2 + 0.8 * value as f32 / max as f32 };
+                        write!(html, "<td style=\"background: rgba(44, 151, 75, {:.2})\" title=\"{} {} &mdash; {} commits\"></td>",
+                               alpha, name, year.date(i, day), value).unwrap();
+                    },
+                    None => html.push_str("<td></td>"),
+                }
+            }
+            html.push_str("</tr>\n");
+        }
+        html.push_str("</table>\n");
+    }
+
+    html
+}
+
+/// Bar chart as inline SVG, every bar has a tooltip with its label and value.
+fn render_bars(series: &[(String, usize)], color: &str) -> String {
+    const HEIGHT: usize = 150;
+    const BAR: usize = 8;
+
+    let max = cmp::max(1, series.iter().map(|&(_, val)| val).max().unwrap_or(0));
+    let width = cmp::max(1, series.len() * BAR);
+
+    let mut html = String::new();
+    write!(html, "<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">\n", width + 40, HEIGHT + 20).unwrap();
+    write!(html, "<text x=\"{}\" y=\"10\">{}</text>\n", width + 4, max).unwrap();
+    write!(html, "<text x=\"{}\" y=\"{}\">0</text>\n", width + 4, HEIGHT + 10).unwrap();
+
+    for (i, &(ref key, val)) in series.iter().enumerate() {
+        let height = val * HEIGHT / max;
+        write!(html, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"><title>{}: {}</title></rect>\n",
+               i * BAR, 10 + HEIGHT - height, BAR - 1, height, color, escape(key), val).unwrap();
+    }
+
+    if let (Some(first), Some(last)) = (series.first(), series.last()) {
+        write!(html, "<text x=\"0\" y=\"{}\">{}</text>\n", HEIGHT + 20, escape(&first.0)).unwrap();
+        write!(html, "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n", width, HEIGHT + 20, escape(&last.0)).unwrap();
+    }
+    html.push_str("</svg>\n");
+
+    html
+}
+
+/// Stacked bar chart of file types per week, either of their shares or of absolute values.
+/// Colours are given by the rank of the type in the latest week.
+fn render_stacked(series: &BTreeMap<String, Breakdown>, relative: bool) -> String {
+    const HEIGHT: usize = 150;
+    const BAR: usize = 8;
+    const COLORS: [&'static str; 8] = ["#0366d6", "#2c974b", "#e36209", "#6f42c1", "#d73a49", "#dbab09", "#005cc5", "#959da5"];
+
+    let latest = series.values().last().map(filetype::ranked).unwrap_or(Vec::new());
+    let color = |name: &str| {
+        latest.iter().position(|&(other, _)| other == name && name != filetype::OTHER)
+            .map_or(COLORS[7], |i| COLORS[cmp::min(i, 7)])
+    };
+
+    let mut html = String::from("<div class=\"legend\">");
+    for &(name, _) in &latest {
+        write!(html, "<span><i style=\"background: {}\"></i>{}</span>", color(name), escape(name)).unwrap();
+    }
+    html.push_str("</div>\n");
+
+    let max = cmp::max(1, series.values().map(|types| types.values().sum()).max().unwrap_or(0));
+    let width = cmp::max(1, series.len() * BAR);
+    write!(html, "<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">\n", width + 40, HEIGHT + 20).unwrap();
+    if relative {
+        write!(html, "<text x=\"{}\" y=\"10\">100%</text>\n", width + 4).unwrap();
+    } else {
+        write!(html, "<text x=\"{}\" y=\"10\">{}</text>\n", width + 4, max).unwrap();
+        write!(html, "<text x=\"{}\" y=\"{}\">0</text>\n", width + 4, HEIGHT + 10).unwrap();
+    }
+
+    for (i, (key, types)) in series.iter().enumerate() {
+        let total = if relative { cmp::max(1, types.values().sum::<usize>()) } else { max };
+        let mut top = 10 + HEIGHT - types.values().sum::<usize>() * HEIGHT / total;
+        for (name, num) in filetype::ranked(types) {
+            let height = num * HEIGHT / total;
+            write!(html, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"><title>{}: {} {}</title></rect>\n",
+                   i * BAR, top, BAR - 1, height, color(name), escape(key), escape(name), num).unwrap();
+            top += height;
+        }
+    }
+
+    if let (Some(first), Some(last)) = (series.keys().next(), series.keys().last()) {
+        write!(html, "<text x=\"0\" y=\"{}\">{}</text>\n", HEIGHT + 20, escape(first)).unwrap();
+        write!(html, "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>\n", width, HEIGHT + 20, escape(last)).unwrap();
+    }
+    html.push_str("</svg>\n");
+
+    html
+}
+
+fn render_authors(authors: &PersonalStats) -> String {
+    let mut html = String::from("<table class=\"sortable\">\n<thead><tr>");
+    for title in &["Author", "Commits", "Insertions", "Deletions", "Owned lines", "Live code", "Age in days", "Active days"] {
+        write!(html, "<th>{}</th>", title).unwrap();
+    }
+    html.push_str("</tr></thead>\n<tbody>\n");
+
+    let rows = authors.rows();
+    for (i, row) in rows.iter().enumerate() {
+        // the last row is the total
+        let class = if i + 1 == rows.len() { " class=\"total\"" } else { "" };
+        write!(html, "<tr{}>", class).unwrap();
+        write!(html, "<td data-value=\"{0}\">{0}</td>", escape(&row.name)).unwrap();
+        write!(html, "<td data-value=\"{0}\">{0} ({1:.2}%)</td>", row.commits, row.commits_percent).unwrap();
+        write!(html, "<td data-value=\"{0}\">{0}</td>", row.insertions).unwrap();
+        write!(html, "<td data-value=\"{0}\">{0}</td>", row.deletions).unwrap();
+        write!(html, "<td data-value=\"{0}\">{0} ({1:.2}%)</td>", row.owned_lines, row.owned_lines_percent).unwrap();
+        write!(html, "<td data-value=\"{0:.2}\">{0:.2}%</td>", row.live_code_percent).unwrap();
+        write!(html, "<td data-value=\"{0}\">{0}</td>", row.age_in_days).unwrap();
+        write!(html, "<td data-value=\"{0}\">{0} ({1:.2}%)</td>", row.active_days, row.active_days_percent).unwrap();
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</tbody>\n</table>\n");
+
+    html
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use git2;
+    use calendar::Calendar;
+    use html::{escape, render_bars, render_calendar, render_stacked};
+
+    #[test]
+    fn smoke() {
+        assert_eq!(escape("name <email>"), "name &lt;email&gt;");
+
+        let svg = render_bars(&[("2015-01".to_string(), 2), ("2015-02".to_string(), 4)], "red");
+        assert!(svg.contains("<title>2015-01: 2</title>"));
+        assert!(svg.contains("height=\"75\""));
+
+        let mut series = BTreeMap::new();
+        series.insert("2015-01".to_string(), vec![("Rust", 3), ("TOML", 1)].into_iter().collect());
+        let svg = render_stacked(&series, true);
+        assert!(svg.contains("<title>2015-01: Rust 3</title>"));
+        assert!(svg.contains("y=\"10\" width=\"7\" height=\"112\""));
+
+        let mut calendar = Calendar::new();
+        // Sun, 28 Jun 2015 13:17:20 +0600
+        calendar.append(&git2::Time::new(1435475840, 6*60));
+        let table = render_calendar(&calendar);
+        assert!(table.contains("<th colspan=\"54\">2015</th>"));
+        assert!(table.contains("title=\"Sun 2015-06-28 &mdash; 1 commits\""));
+    }
+}
@@ -0,0 +1,105 @@
+use serde_json;
+
+use filetype::Breakdown;
+use mailmap::Identity;
+use personal::Stat;
+use report::Report;
+
+/// The whole report, see README for the description of the fields.
+#[derive(Serialize)]
+struct Document<'a> {
+    heatmap: Vec<Vec<u32>>,
+    heatmap_timezone: String,
+    calendar: Vec<Year>,
+    authors: Vec<Author>,
+    total: Author,
+    weekly_activity: Vec<Week>,
+    files: Vec<Week>,
+    file_types: Vec<Types<'a>>,
+    lines: Vec<Lines<'a>>,
+    non_utf8_identities: usize,
+}
+
+#[derive(Serialize)]
+struct Author {
+    name: String,
+    commits: usize,
+    insertions: usize,
+    deletions: usize,
+    owned_lines: usize,
+    first_commit: Option<String>,
+    last_commit: Option<String>,
+    active_days: usize,
+    weekly_activity: Vec<Week>,
+}
+
+#[derive(Serialize)]
+struct Week {
+    week: String,
+    value: usize,
+}
+
+#[derive(Serialize)]
+struct Year {
+    year: i32,
+    weeks: Vec<[Option<u32>; 7]>,
+}
+
+#[derive(Serialize)]
+struct Types<'a> {
+    week: &'a str,
+    types: &'a Breakdown,
+}
+
+#[derive(Serialize)]
+struct Lines<'a> {
+    week: &'a str,
+    total: usize,
+    types: &'a Breakdown,
+}
+
+impl Author {
+    fn new(name: String, stat: &Stat) -> Author {
+        Author {
+            name: name,
+            commits: stat.num_commit(),
+            insertions: stat.insertions(),
+            deletions: stat.deletions(),
+            owned_lines: stat.num_lines(),
+            first_commit: stat.first_commit().map(|datetime| datetime.to_rfc3339()),
+            last_commit: stat.last_commit().map(|datetime| datetime.to_rfc3339()),
+            active_days: stat.num_active_days(),
+            weekly_activity: weeks(stat.activity_weeks().iter().map(|(key, &val)| (key.clone(), val))),
+        }
+    }
+}
+
+fn weeks<I: Iterator<Item=(String, usize)>>(iter: I) -> Vec<Week> {
+    iter.map(|(week, value)| Week { week: week, value: value }).collect()
+}
+
+pub fn render(report: &Report) -> Result<String, serde_json::Error> {
+    let total = report.authors.total();
+
+    let mut authors: Vec<(&Identity, &Stat)> = report.authors.iter().collect();
+    authors.sort_by(|a, b| a.0.cmp(b.0));
+    let mut authors: Vec<Author> = authors.into_iter().map(|(identity, stat)| Author::new(identity.to_string(), stat)).collect();
+    authors.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let document = Document {
+        heatmap: (0..7).map(|day| (0..24).map(|hour| report.heatmap.get(day, hour)).collect()).collect(),
+        heatmap_timezone: report.heatmap.timezone().to_string(),
+        calendar: report.calendar.years().iter().map(|year| Year { year: year.year(), weeks: year.weeks() }).collect(),
+        authors: authors,
+        total: Author::new("Total".to_string(), &total),
+        weekly_activity: weeks(report.authors.weeks().into_iter()),
+        files: weeks(report.files.iter().map(|(key, &val)| (key.clone(), val))),
+        file_types: report.file_types.iter().map(|(week, types)| Types { week: week, types: types }).collect(),
+        lines: report.lines.iter()
+            .map(|(week, types)| Lines { week: week, total: types.values().sum(), types: types })
+            .collect(),
+        non_utf8_identities: report.non_utf8_identities,
+    };
+
+    serde_json::to_string_pretty(&document)
+}
@@ -0,0 +1,88 @@
+//! Tool for obtaining different kind of information from your git repository.
+//!
+//! The main entry point is [`analyse`](fn.analyse.html), which walks the history selected by
+//! [`Options`](struct.Options.html) and returns a [`Report`](struct.Report.html) that can be
+//! rendered as text, JSON, CSV or HTML. The building blocks (`Heatmap`, `PersonalStats`,
+//! `Mailmap`, `HasSnapshot`, ...) may also be used on their own.
+//!
+//! ```no_run
+//! extern crate git2;
+//! extern crate gitostat;
+//!
+//! let repo = git2::Repository::open(".").unwrap();
+//! let mut options = gitostat::Options::new();
+//! options.revisions = gitostat::Revisions::new(&["v1.0..v2.0".to_string()]);
+//!
+//! let report = gitostat::analyse(&repo, &options).unwrap();
+//! println!("{}", report.to_json().unwrap());
+//! ```
+
+extern crate git2;
+extern crate chrono;
+#[macro_use]
+extern crate serde_derive;
+extern crate core;
+extern crate regex;
+extern crate serde_json;
+#[macro_use]
+extern crate prettytable;
+extern crate atty;
+#[cfg(test)] extern crate tempdir;
+
+macro_rules! error(
+    ($($arg:tt)*) => (
+        use std::io::Write;
+        match writeln!(&mut ::std::io::stderr(), $($arg)* ) {
+            Ok(_) => {},
+            Err(x) => panic!("Unable to write to stderr: {}", x),
+        }
+    )
+);
+
+/// writes progress into stderr, so it doesn't mix with the report.
+macro_rules! progress(
+    ($($arg:tt)*) => (
+        use std::io::Write;
+        match write!(&mut ::std::io::stderr(), $($arg)* ) {
+            Ok(_) => {},
+            Err(x) => panic!("Unable to write to stderr: {}", x),
+        }
+    )
+);
+
+pub mod error;
+pub mod date;
+pub mod snapshot;
+pub mod filetype;
+pub mod loc;
+pub mod heatmap;
+pub mod calendar;
+pub mod mailmap;
+pub mod personal;
+pub mod revision;
+pub mod pathspec;
+pub mod cache;
+pub mod role;
+pub mod lint;
+pub mod style;
+pub mod suggest;
+mod report;
+mod json;
+mod csv;
+mod html;
+#[cfg(test)] mod test;
+
+pub use cache::{Cache, CacheMode};
+pub use calendar::Calendar;
+pub use date::Window;
+pub use error::Error;
+pub use heatmap::{Heatmap, Timezone};
+pub use loc::LineCounter;
+pub use mailmap::{Identity, Mailmap};
+pub use pathspec::Pathspec;
+pub use personal::{HasStat, PersonalStats, Stat};
+pub use report::{analyse, Merges, Options, Report};
+pub use revision::Revisions;
+pub use role::Role;
+pub use snapshot::{HasSnapshot, Snapshot};
+pub use style::{ColorChoice, Style};
@@ -0,0 +1,250 @@
+use std::{cmp, fmt};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use git2;
+
+use error::Error;
+use mailmap::{self, Entry, Identity, Line, Mailmap, Source};
+use revision::Revisions;
+
+/// Problem found in mailmap.
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    /// Name of the source and the 1-based line, `None` for problems of the history rather than of a line.
+    pub location: Option<Location>,
+    pub message: String,
+}
+
+/// Line of one of the mailmap sources.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Location {
+    /// Index of the source, they are ordered as they are read.
+    pub source: usize,
+    pub name: String,
+    pub line: usize,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some(ref location) => write!(f, "{}:{}: {}", location.name, location.line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl Location {
+    /// `line N` in the same source as `other`, `line N of <name>` in another one.
+    fn relative_to(&self, other: &Location) -> String {
+        if self.source == other.source {
+            format!("line {}", self.line)
+        } else {
+            format!("line {} of {}", self.line, self.name)
+        }
+    }
+}
+
+/// Value set by an entry and the location of the entry.
+type Setting = (Option<Vec<u8>>, Location);
+
+/// Checks the mailmap sources, in the order git reads them, against the authors and committers
+/// of the commits: lines git skips, duplicate and conflicting entries, entries which match no commit,
+/// and emails of commits shown as several identities after the mailmap.
+pub fn lint(repo: &git2::Repository, revisions: &Revisions, sources: &[Source]) -> Result<Vec<Problem>, Error> {
+    let mut problems = Vec::new();
+    let problem = |location: &Location, message: String| Problem { location: Some(location.clone()), message: message };
+
+    let mut entries: Vec<(Location, Entry)> = Vec::new();
+    for (source, &Source { ref name, ref content }) in sources.iter().enumerate() {
+        for (i, line) in content.split(|&byte| byte == b'\n').enumerate() {
+            let location = Location { source: source, name: name.clone(), line: i + 1 };
+            match mailmap::parse_line(line) {
+                Line::Blank => {},
+                Line::Invalid => problems.push(problem(&location, "no email, the line is skipped".to_string())),
+                Line::Entry(entry) => {
+                    if line.iter().skip_while(|&&byte| byte == b' ' || byte == b'\t').next() == Some(&b'#') {
+                        problems.push(problem(&location, "`#` isn't at the start of the line, so it isn't a comment".to_string()));
+                    }
+                    entries.push((location, entry));
+                }
+            }
+        }
+    }
+
+    // names and emails of the entries for the whole email, they are set separately
+    let mut names: HashMap<Vec<u8>, Setting> = HashMap::new();
+    let mut emails: HashMap<Vec<u8>, Setting> = HashMap::new();
+    // identities of the entries for the exact name
+    let mut identities: HashMap<(Vec<u8>, Vec<u8>), (Identity, Location)> = HashMap::new();
+    for &(ref location, ref entry) in &entries {
+        let email = entry.old_email.to_ascii_lowercase();
+        let (duplicate, conflict) = match entry.old_name {
+            Some(ref name) => {
+                let target = Identity {
+                    name: entry.new_name.clone().unwrap_or_default(),
+                    email: entry.new_email.clone().unwrap_or_default(),
+                };
+                match identities.insert((email, name.to_ascii_lowercase()), (target.clone(), location.clone())) {
+                    Some((previous, other)) => if previous == target { (Some(other), None) } else { (None, Some(other)) },
+                    None => (None, None),
+                }
+            },
+            None => {
+                let name = set(&mut names, &email, &entry.new_name, location);
+                let email = set(&mut emails, &email, &entry.new_email, location);
+                match (name, email) {
+                    (Err(other), _) | (_, Err(other)) => (None, Some(other)),
+                    (Ok(Some(other)), Ok(None)) | (Ok(None), Ok(Some(other))) => (Some(other), None),
+                    (Ok(Some(name)), Ok(Some(email))) => (Some(cmp::max(name, email)), None),
+                    (Ok(None), Ok(None)) => (None, None),
+                }
+            }
+        };
+        if let Some(other) = conflict {
+            problems.push(problem(location, format!("conflicts with {}, the later one wins", other.relative_to(location))));
+        } else if let Some(other) = duplicate {
+            problems.push(problem(location, format!("duplicate of {}", other.relative_to(location))));
+        }
+    }
+
+    // identities of commits by the lowercased email
+    let mut history: BTreeMap<Vec<u8>, BTreeSet<Identity>> = BTreeMap::new();
+    let mut revwalk = repo.revwalk()?;
+    revisions.push(repo, &mut revwalk)?;
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        for signature in &[commit.author(), commit.committer()] {
+            let identity = Identity::new(signature);
+            history.entry(identity.email.to_ascii_lowercase()).or_insert_with(BTreeSet::new).insert(identity);
+        }
+    }
+
+    let named: HashSet<(Vec<u8>, Vec<u8>)> = history.values()
+        .flat_map(|identities| identities.iter())
+        .map(|identity| (identity.email.to_ascii_lowercase(), identity.name.to_ascii_lowercase()))
+        .collect();
+    for &(ref location, ref entry) in &entries {
+        let email = entry.old_email.to_ascii_lowercase();
+        let used = match entry.old_name {
+            Some(ref name) => named.contains(&(email, name.to_ascii_lowercase())),
+            None => history.contains_key(&email),
+        };
+        if !used {
+            let old = Identity { name: entry.old_name.clone().unwrap_or_default(), email: entry.old_email.clone() };
+            problems.push(problem(location, format!("no commits by {}", String::from_utf8_lossy(&old.to_bytes()))));
+        }
+    }
+
+    let mut mailmap = Mailmap::empty();
+    for source in sources {
+        mailmap.read(&source.content[..])?;
+    }
+    for (_, identities) in &history {
+        let mapped: BTreeSet<Identity> = identities.iter().map(|identity| mailmap.map(identity)).collect();
+        // several people sharing an email are fine if every one of them is mapped
+        let unmapped = identities.iter().any(|identity| mailmap.lookup(&identity.name, &identity.email).is_none());
+        if mapped.len() > 1 && unmapped {
+            let raw: Vec<String> = identities.iter().map(|identity| identity.to_string()).collect();
+            let mapped: Vec<String> = mapped.iter().map(|identity| identity.to_string()).collect();
+            problems.push(Problem {
+                location: None,
+                message: format!("{} are shown as several identities: {}", raw.join(", "), mapped.join(", ")),
+            });
+        }
+    }
+
+    // history problems go last
+    problems.sort_by(|a, b| (a.location.is_none(), &a.location).cmp(&(b.location.is_none(), &b.location)));
+    Ok(problems)
+}
+
+/// Records the value set by the entry at the location. The location of the same value set before is `Ok`,
+/// the location of a different one is `Err`.
+fn set(settings: &mut HashMap<Vec<u8>, Setting>, email: &[u8], value: &Option<Vec<u8>>, location: &Location)
+       -> Result<Option<Location>, Location> {
+    if value.is_none() {
+        return Ok(None);
+    }
+    match settings.insert(email.to_vec(), (value.clone(), location.clone())) {
+        Some((previous, other)) => if previous == *value { Ok(Some(other)) } else { Err(other) },
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git2;
+    use mailmap::Source;
+    use revision::Revisions;
+    use lint::lint;
+
+    fn worktree(content: &[u8]) -> Vec<Source> {
+        vec![Source { name: ".mailmap".to_string(), content: content.to_vec() }]
+    }
+
+    #[test]
+    fn smoke() {
+        let (_td, repo) = ::test::repo_init();
+        ::test::commit(&repo, "a", "a");
+
+        let content = b"\
+# identities of the test repository
+Name <email>
+<proper@example.com> <email>
+Nobody <nobody@example.com>
+no email here
+Name <email>
+Other Name <email>
+  # Indented <email>
+Proper <proper@example.com> name <email>
+Proper <proper@example.com> NAME <EMAIL>
+";
+        let problems = lint(&repo, &Revisions::new(&[]), &worktree(content)).unwrap();
+        let problems: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(problems, vec![
+            ".mailmap:4: no commits by <nobody@example.com>",
+            ".mailmap:5: no email, the line is skipped",
+            ".mailmap:6: duplicate of line 2",
+            ".mailmap:7: conflicts with line 6, the later one wins",
+            ".mailmap:8: `#` isn't at the start of the line, so it isn't a comment",
+            ".mailmap:8: conflicts with line 7, the later one wins",
+            ".mailmap:10: duplicate of line 9",
+        ]);
+
+        assert!(lint(&repo, &Revisions::new(&[]), &worktree(b"Proper <proper@example.com> <email>\n")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn sources() {
+        let (_td, repo) = ::test::repo_init();
+        let sources = vec![
+            Source { name: ".mailmap".to_string(), content: b"Name <email>\n".to_vec() },
+            Source { name: "HEAD:.mailmap".to_string(), content: b"\nOther <email>\nName <email>\n".to_vec() },
+        ];
+        let problems = lint(&repo, &Revisions::new(&[]), &sources).unwrap();
+        let problems: Vec<String> = problems.iter().map(|problem| problem.to_string()).collect();
+        assert_eq!(problems, vec![
+            "HEAD:.mailmap:2: conflicts with line 1 of .mailmap, the later one wins",
+            "HEAD:.mailmap:3: conflicts with line 2, the later one wins",
+        ]);
+    }
+
+    #[test]
+    fn several_identities() {
+        let (_td, repo) = ::test::repo_init();
+        let head = repo.find_commit(repo.refname_to_id("HEAD").unwrap()).unwrap();
+        let other = git2::Signature::new("other", "email", &git2::Time::new(1435475840, 0)).unwrap();
+        repo.commit(Some("HEAD"), &other, &other, "other", &head.tree().unwrap(), &[&head]).unwrap();
+
+        // the name-specific entry leaves the other name of the email as it is
+        let problems = lint(&repo, &Revisions::new(&[]), &worktree(b"Proper <proper@example.com> other <email>\n")).unwrap();
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].location, None);
+        assert_eq!(problems[0].message, "name <email>, other <email> are shown as several identities: \
+                                         Proper <proper@example.com>, name <email>");
+
+        // unless both are mapped
+        let content = b"Proper <proper@example.com> other <email>\nName <name@example.com> name <email>\n";
+        assert!(lint(&repo, &Revisions::new(&[]), &worktree(content)).unwrap().is_empty());
+        assert!(lint(&repo, &Revisions::new(&[]), &worktree(b"Proper <proper@example.com> <email>\n")).unwrap().is_empty());
+    }
+}
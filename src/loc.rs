@@ -0,0 +1,81 @@
+use git2;
+use std::collections::HashMap;
+
+use filetype::{self, Breakdown};
+use snapshot::Snapshot;
+
+/// Counts lines of code in snapshots. Blobs are read only once, the number of lines
+/// is cached by the blob id, so unchanged files cost nothing in the next snapshots.
+pub struct LineCounter<'repo> {
+    repo: &'repo git2::Repository,
+    cache: HashMap<git2::Oid, usize>,
+}
+
+impl<'repo> LineCounter<'repo> {
+    pub fn new(repo: &'repo git2::Repository) -> LineCounter<'repo> {
+        LineCounter {
+            repo: repo,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// Number of lines in the blob, binary blobs have none.
+    pub fn lines(&mut self, id: git2::Oid) -> Result<usize, git2::Error> {
+        if let Some(&lines) = self.cache.get(&id) {
+            return Ok(lines);
+        }
+
+        let blob = self.repo.find_blob(id)?;
+        let lines = if blob.is_binary() { 0 } else { count_lines(blob.content()) };
+        self.cache.insert(id, lines);
+
+        Ok(lines)
+    }
+
+    /// Number of lines of each file type in the snapshot.
+    pub fn count(&mut self, snapshot: &Snapshot) -> Result<Breakdown, git2::Error> {
+        let mut result = Breakdown::new();
+        for (path, &id) in snapshot.blobs() {
+            let lines = self.lines(id)?;
+            *result.entry(filetype::classify(path)).or_insert(0) += lines;
+        }
+        Ok(result)
+    }
+}
+
+/// The last line is counted even if it isn't terminated by a line break.
+fn count_lines(content: &[u8]) -> usize {
+    let breaks = content.iter().filter(|&&byte| byte == b'\n').count();
+    match content.last() {
+        Some(&b'\n') | None => breaks,
+        Some(_) => breaks + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use snapshot::HasSnapshot;
+    use role::Role;
+    use loc::{count_lines, LineCounter};
+
+    #[test]
+    fn smoke() {
+        assert_eq!(count_lines(b""), 0);
+        assert_eq!(count_lines(b"one\ntwo\n"), 2);
+        assert_eq!(count_lines(b"one\ntwo"), 2);
+
+        let (_td, repo) = ::test::repo_init();
+        ::test::commit(&repo, "src/main.rs", "fn main() {\n}\n");
+        ::test::commit(&repo, "src/lib.rs", "fn main() {\n}\n");
+        let id = ::test::commit(&repo, "README.md", "# Title\n");
+        let commit = repo.find_commit(id).unwrap();
+        let snapshot = repo.snapshot(&commit, false, None, Role::Author).unwrap();
+
+        let mut counter = LineCounter::new(&repo);
+        let lines = counter.count(&snapshot).unwrap();
+        assert_eq!(lines.get("Rust"), Some(&4));
+        assert_eq!(lines.get("Markdown"), Some(&1));
+        // both rust files have the same content
+        assert_eq!(counter.cache.len(), 2);
+    }
+}
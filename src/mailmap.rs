@@ -1,34 +1,21 @@
-use std::path::Path;
-use std::{fmt, default};
-use std::io::{BufReader,BufRead};
+use std::path::{Path, PathBuf};
+use std::{env, fmt, default, io};
+use std::io::{BufReader, BufRead, Read};
 use std::fs::File;
 use std::collections::HashMap;
-use std::string;
 use git2;
-use regex::Regex;
-
-macro_rules! ostring {
-    ($e:expr) => (match $e {
-        Some(s) => Some(String::from(s)),
-        None => None
-    })
-}
-
-macro_rules! matchtostring {
-    ($e:expr) => (match $e {
-        Some(m) => Some(String::from(m.as_str())),
-        None => None
-    })
-}
+use error::Error;
 
+/// Names and emails are kept as bytes, they aren't necessarily UTF-8.
+#[derive(Clone)]
 struct Author {
-    name: Option<String>,
-    email: Option<String>,
-    namemap: HashMap<String, Author>
+    name: Option<Vec<u8>>,
+    email: Option<Vec<u8>>,
+    namemap: HashMap<Vec<u8>, Author>
 }
 
 impl Author {
-    pub fn new(name: Option<String>, email: Option<String>) -> Author {
+    pub fn new(name: Option<Vec<u8>>, email: Option<Vec<u8>>) -> Author {
         Author {
             name: name,
             email: email,
@@ -36,17 +23,17 @@ impl Author {
         }
     }
 
-    pub fn set_name(&mut self, name: Option<String>) -> &mut Author {
+    pub fn set_name(&mut self, name: Option<Vec<u8>>) -> &mut Author {
         self.name = name;
         self
     }
 
-    pub fn set_email(&mut self, email: Option<String>) -> &mut Author {
+    pub fn set_email(&mut self, email: Option<Vec<u8>>) -> &mut Author {
         self.email = email;
         self
     }
 
-    pub fn namemap_insert(&mut self, name: String, author: Author) -> &mut Author {
+    pub fn namemap_insert(&mut self, name: Vec<u8>, author: Author) -> &mut Author {
         self.namemap.insert(name, author);
         self
     }
@@ -64,80 +51,467 @@ impl default::Default for Author {
 
 impl fmt::Display for Author {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?} <{:?}>", self.name, self.email)
+        write!(f, "{:?} <{:?}>", self.name.as_ref().map(|name| String::from_utf8_lossy(name)),
+               self.email.as_ref().map(|email| String::from_utf8_lossy(email)))
     }
 }
 
 impl fmt::Debug for Author {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{:?} <{:?}> {:?}", self.name, self.email, self.namemap)
+        write!(f, "{} {:?}", self, self.namemap)
     }
 }
 
+/// Name and email of a signature as they are, not necessarily UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Identity {
+    pub name: Vec<u8>,
+    pub email: Vec<u8>,
+}
+
+impl Identity {
+    pub fn new(signature: &git2::Signature) -> Identity {
+        Identity { name: signature.name_bytes().to_vec(), email: signature.email_bytes().to_vec() }
+    }
+
+    /// `Name <email>` as written in mailmap, just `<email>` without the name.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut result = Vec::new();
+        if !self.name.is_empty() {
+            result.extend_from_slice(&self.name);
+            result.push(b' ');
+        }
+        result.push(b'<');
+        result.extend_from_slice(&self.email);
+        result.push(b'>');
+        result
+    }
+}
+
+impl fmt::Display for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", identity(&self.name, &self.email))
+    }
+}
+
+/// Mapping of commit identities to the proper ones, with the semantics of git's `.mailmap`:
+/// emails and names are matched case-insensitively, later lines override earlier ones.
+/// Entries are matched byte by byte, so identities in legacy encodings are mapped as well.
+#[derive(Clone)]
 pub struct Mailmap {
-    items: HashMap<String, Author>
+    /// Keyed by the commit email, lowercased in ASCII.
+    items: HashMap<Vec<u8>, Author>
 }
 
 impl Mailmap {
 
-    pub fn new(path: &Path) -> Option<Mailmap> {
+    /// Reads mailmap from the file, returns `None` if there is no such file.
+    pub fn new(path: &Path) -> Result<Option<Mailmap>, Error> {
         let file = match File::open(path) {
             Ok(file) => file,
-            Err(_) => return None
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(Error::from(err))
         };
 
-        let reader = BufReader::new(file);
+        let mut mailmap = Mailmap::empty();
+        mailmap.read(BufReader::new(file))?;
+        Ok(Some(mailmap))
+    }
 
-        // for more help with this regex see https://www.debuggex.com/r/eF5E6HQm4aAhXEtN
-        let re = Regex::new(r"^((?P<new_name>.+?)\s+)??<\s*(?P<new_email>.+?)\s*>((\s+(?P<old_name>.+?))??\s+<\s*(?P<old_email>.+?)\s*>)?").unwrap();
+    /// Reads mailmap of the repository the way git does, later sources override earlier ones,
+    /// see `sources`. `None` if there are no sources.
+    pub fn from_repo(repo: &git2::Repository) -> Result<Option<Mailmap>, Error> {
+        let sources = sources(repo)?;
+        if sources.is_empty() {
+            return Ok(None);
+        }
 
-        let mut authors: HashMap<String, Author> = HashMap::new();
+        let mut mailmap = Mailmap::empty();
+        for source in &sources {
+            mailmap.read(&source.content[..])?;
+        }
+        Ok(Some(mailmap))
+    }
 
-        for line in reader.lines() {
-            let line = match line {
-                Ok(line) => line,
-                Err(_) => continue
-            };
+    pub fn empty() -> Mailmap {
+        Mailmap { items: HashMap::new() }
+    }
 
-            // no comments
-            if line.chars().nth(0) == Some('#') { continue; }
+    /// Adds the entries of the mailmap content, they override the ones read before.
+    ///
+    /// Lines are parsed as git does: `#` at the start of a line begins a comment,
+    /// anything after the last email is ignored, lines without an email are skipped.
+    pub fn read<R: BufRead>(&mut self, reader: R) -> Result<(), Error> {
+        for line in reader.split(b'\n') {
+            if let Line::Entry(entry) = parse_line(&line?) {
+                self.add(entry);
+            }
+        }
 
-            if let Some(caps) = re.captures(&line[..]) {
-                let mut old_email = matchtostring!(caps.name("old_email"));
-                let old_name = matchtostring!(caps.name("old_name"));
-                let mut new_email = matchtostring!(caps.name("new_email"));
-                let new_name = matchtostring!(caps.name("new_name"));
+        Ok(())
+    }
 
-                if old_email.is_none() {
-                    old_email = new_email.clone();
-                    new_email = None;
-                }
 
-                let me = authors.entry(old_email.unwrap()).or_insert(Author::default());
+    fn add(&mut self, entry: Entry) {
+        let Entry { old_name, old_email, new_name, new_email } = entry;
+        let me = self.items.entry(old_email.to_ascii_lowercase()).or_insert(Author::default());
 
-                if let Some(name) = old_name {
-                    me.namemap_insert(name, Author::new(new_name, new_email));
-                } else {
-                    me.set_name(new_name).set_email(new_email);
-                }
+        match old_name {
+            Some(old_name) => {
+                me.namemap_insert(old_name.to_ascii_lowercase(), Author::new(new_name, new_email));
+            },
+            // only the given parts are replaced, so `Name <commit>` and `<proper> <commit>` combine
+            None => {
+                if new_name.is_some() { me.set_name(new_name); }
+                if new_email.is_some() { me.set_email(new_email); }
             }
         }
+    }
+
+    /// Proper name and email of the identity, `None` if the mailmap doesn't change it.
+    pub fn lookup(&self, name: &[u8], email: &[u8]) -> Option<(Vec<u8>, Vec<u8>)> {
+        let item = match self.items.get(&email.to_ascii_lowercase()) {
+            Some(item) => item,
+            None => return None
+        };
+        // entries for the exact name take precedence over the ones for the whole email
+        let author = item.namemap.get(&name.to_ascii_lowercase()).unwrap_or(item);
+        if author.name.is_none() && author.email.is_none() {
+            return None;
+        }
+
+        Some((author.name.clone().unwrap_or_else(|| name.to_vec()),
+              author.email.clone().unwrap_or_else(|| email.to_vec())))
+    }
+
+    /// Proper identity, the same one if the mailmap doesn't change it.
+    pub fn map(&self, identity: &Identity) -> Identity {
+        match self.lookup(&identity.name, &identity.email) {
+            Some((name, email)) => Identity { name: name, email: email },
+            None => identity.clone()
+        }
+    }
+
+    /// `Name <email>` of the mapped identity, invalid UTF-8 is replaced for display.
+    pub fn map_user(&self, signature: &git2::Signature) -> String {
+        match self.lookup(signature.name_bytes(), signature.email_bytes()) {
+            Some((name, email)) => identity(&name, &email),
+            None => identity(signature.name_bytes(), signature.email_bytes())
+        }
+    }
+
+}
+
+/// Line of mailmap.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Line {
+    /// Empty line or a comment.
+    Blank,
+    /// Line without an email, git skips it.
+    Invalid,
+    Entry(Entry),
+}
+
+/// Mapping of the commit identity, `old_name` is `None` for all names with the email.
+/// `new_email` is `None` if only the name is mapped.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub old_name: Option<Vec<u8>>,
+    pub old_email: Vec<u8>,
+    pub new_name: Option<Vec<u8>>,
+    pub new_email: Option<Vec<u8>>,
+}
+
+/// Parses the line without its line break, with the same rules as git.
+pub fn parse_line(line: &[u8]) -> Line {
+    if line.starts_with(b"#") || trim(line).is_empty() {
+        return Line::Blank;
+    }
+
+    let (new_name, new_email, rest) = match parse_name_and_email(line, false) {
+        Some(parsed) => parsed,
+        None => return Line::Invalid
+    };
+    let to_vec = |bytes: Option<&[u8]>| bytes.map(|bytes| bytes.to_vec());
+    Line::Entry(match parse_name_and_email(rest, true) {
+        Some((old_name, old_email, _)) => Entry {
+            old_name: to_vec(old_name), old_email: old_email.to_vec(),
+            new_name: to_vec(new_name), new_email: Some(new_email.to_vec()),
+        },
+        // `Proper Name <commit@email>`
+        None => Entry { old_name: None, old_email: new_email.to_vec(), new_name: to_vec(new_name), new_email: None },
+    })
+}
+
+/// Mailmap content of a repository and where it is read from.
+pub struct Source {
+    /// `.mailmap` of the worktree, the revision of `mailmap.blob` or the path of `mailmap.file`.
+    pub name: String,
+    pub content: Vec<u8>,
+}
+
+/// Mailmap contents of the repository in the order git reads them: `.mailmap` of the worktree,
+/// the blob of `mailmap.blob` (`HEAD:.mailmap` in bare repositories) and the file of `mailmap.file`.
+/// Missing sources are skipped.
+pub fn sources(repo: &git2::Repository) -> Result<Vec<Source>, Error> {
+    let mut sources = Vec::new();
+
+    if let Some(workdir) = repo.workdir() {
+        if let Some(content) = read_file(&workdir.join(".mailmap"))? {
+            sources.push(Source { name: ".mailmap".to_string(), content: content });
+        }
+    }
 
-        Some(Mailmap {
-            items: authors
-        })
+    let config = repo.config()?;
+    let blob = match config_string(&config, "mailmap.blob")? {
+        Some(blob) => Some(blob),
+        None if repo.is_bare() => Some("HEAD:.mailmap".to_string()),
+        None => None
+    };
+    if let Some(blob) = blob {
+        // like git, a revision which doesn't resolve isn't an error
+        match repo.revparse_single(&blob) {
+            Ok(object) => {
+                let content = object.as_blob()
+                    .ok_or_else(|| Error::Mailmap { name: blob.clone(), message: "not a blob".to_string() })?
+                    .content().to_vec();
+                sources.push(Source { name: blob, content: content });
+            },
+            Err(ref err) if err.code() == git2::ErrorCode::NotFound => {},
+            Err(err) => return Err(Error::from(err))
+        }
     }
 
-    pub fn map_user(&self, signature: &git2::Signature) -> Result<String, string::FromUtf8Error> {
-        let name =  ostring!(signature.name()).expect("Valid utf8 string");
-        let email = ostring!(signature.email()).expect("Valid utf8 string");
-        let author = match self.items.get(&email) {
-            Some(item) => if item.namemap.is_empty() { Some(item) } else { item.namemap.get(&name) },
-            None => None
+    if let Some(file) = config_string(&config, "mailmap.file")? {
+        // `~/` is expanded with $HOME as git does
+        let path = match (file.starts_with("~/"), env::var_os("HOME")) {
+            (true, Some(home)) => PathBuf::from(home).join(&file[2..]),
+            _ => PathBuf::from(file),
         };
+        // relative paths are taken from the worktree, or from the repository itself if it's bare
+        let path = repo.workdir().unwrap_or(repo.path()).join(path);
+        if let Some(content) = read_file(&path)? {
+            sources.push(Source { name: path.display().to_string(), content: content });
+        }
+    }
 
-        Ok(author.map_or_else(|| format!("{}", signature), |item| format!("{} <{}>", item.name.clone().unwrap_or(name), item.email.clone().unwrap_or(email))))
+    Ok(sources)
+}
+
+/// Content of the file, `None` if there is no such file.
+fn read_file(path: &Path) -> Result<Option<Vec<u8>>, Error> {
+    match File::open(path) {
+        Ok(mut file) => {
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            Ok(Some(content))
+        },
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(Error::from(err)),
+    }
+}
+
+/// Value of the config entry, `None` if it isn't set.
+fn config_string(config: &git2::Config, name: &str) -> Result<Option<String>, Error> {
+    let entry = match config.get_entry(name) {
+        Ok(entry) => entry,
+        Err(ref err) if err.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(err) => return Err(Error::from(err))
+    };
+    match entry.value() {
+        Some(value) => Ok(Some(value.to_string())),
+        None => Err(Error::Encoding(format!("{} {:?} is not valid utf8", name, String::from_utf8_lossy(entry.value_bytes()))))
+    }
+}
+
+/// `Name <email>` for display, invalid UTF-8 sequences are replaced by U+FFFD.
+pub fn identity(name: &[u8], email: &[u8]) -> String {
+    format!("{} <{}>", String::from_utf8_lossy(name), String::from_utf8_lossy(email))
+}
+
+/// Splits `Name <email> rest` into the trimmed name, the email as is and the rest, like git's
+/// `parse_name_and_email`. The empty email is accepted only when `allow_empty_email` is set.
+fn parse_name_and_email(line: &[u8], allow_empty_email: bool) -> Option<(Option<&[u8]>, &[u8], &[u8])> {
+    let left = match line.iter().position(|&byte| byte == b'<') {
+        Some(left) => left,
+        None => return None
+    };
+    let right = match line[left + 1..].iter().position(|&byte| byte == b'>') {
+        Some(right) => left + 1 + right,
+        None => return None
+    };
+    if !allow_empty_email && right == left + 1 {
+        return None;
     }
 
+    let name = trim(&line[..left]);
+    let name = if name.is_empty() { None } else { Some(name) };
+    Some((name, &line[left + 1..right], &line[right + 1..]))
+}
+
+/// Strips ASCII whitespace, as git's `isspace` does.
+fn trim(bytes: &[u8]) -> &[u8] {
+    let is_space = |byte: &u8| *byte == b' ' || *byte == b'\t' || *byte == b'\n' || *byte == b'\r'
+                               || *byte == b'\x0b' || *byte == b'\x0c';
+    let start = bytes.iter().position(|byte| !is_space(byte)).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|byte| !is_space(byte)).map_or(start, |end| end + 1);
+    &bytes[start..end]
 }
 
+
+#[cfg(test)]
+mod tests {
+    use std::fs::{File, OpenOptions};
+    use std::io::prelude::*;
+    use tempdir::TempDir;
+    use git2;
+    use mailmap::{identity, sources, Mailmap};
+    use error::Error;
+
+    /// Expected identities are the output of `git check-mailmap` for the same mailmap.
+    #[test]
+    fn conformance() {
+        let content = "\
+# comment <ignored@example.com>
+Joe Developer <joe@example.com>
+<jane@example.com> <jane@laptop.(none)>
+Jane Doe <jane@example.com> jane <jane@desktop.(none)>
+Other Author <other@author.xx>   nick1 <bugs@company.xx>
+Some Dude <some@dude.xx>         nick2 <BUGS@Company.xx>
+Santa Claus <santa.claus@northpole.xx> <me@company.xx>
+Repo Guy <repo@example.com> # trailing comment
+Old Name <old@example.com>
+New Name <old@example.com>
+Full Name <split@example.com>
+<proper@example.com> <split@example.com>
+<fallback@example.com> <both@example.com>
+Specific <specific@example.com> Nick <both@example.com>
+  # not a comment <weird@example.com>
+Empty Email <empty@example.com> <>
+no email at all
+";
+        let mut mailmap = Mailmap::empty();
+        mailmap.read(content.as_bytes()).unwrap();
+
+        let cases = [
+            // email only, matched case-insensitively, the commit email is kept
+            (("Joe", "joe@example.com"), "Joe Developer <joe@example.com>"),
+            (("x", "JOE@Example.COM"), "Joe Developer <JOE@Example.COM>"),
+            // email replaced, the name is kept
+            (("jane", "jane@laptop.(none)"), "jane <jane@example.com>"),
+            // name and email, the name is matched case-insensitively
+            (("JANE", "jane@desktop.(none)"), "Jane Doe <jane@example.com>"),
+            (("other", "jane@desktop.(none)"), "other <jane@desktop.(none)>"),
+            // one email shared by several names
+            (("nick1", "bugs@company.xx"), "Other Author <other@author.xx>"),
+            (("NICK2", "bugs@company.xx"), "Some Dude <some@dude.xx>"),
+            (("nick3", "bugs@company.xx"), "nick3 <bugs@company.xx>"),
+            (("santa", "me@company.xx"), "Santa Claus <santa.claus@northpole.xx>"),
+            // anything after the email is ignored
+            (("r", "repo@example.com"), "Repo Guy <repo@example.com>"),
+            // later lines override earlier ones
+            (("o", "old@example.com"), "New Name <old@example.com>"),
+            // name and email given by separate lines combine
+            (("s", "split@example.com"), "Full Name <proper@example.com>"),
+            // entries for the name take precedence, the rest falls back to the entry for the email
+            (("nick", "both@example.com"), "Specific <specific@example.com>"),
+            (("someone", "both@example.com"), "someone <fallback@example.com>"),
+            // only `#` at the start of a line is a comment
+            (("w", "weird@example.com"), "# not a comment <weird@example.com>"),
+            (("x", ""), "Empty Email <empty@example.com>"),
+            (("x", "unknown@example.com"), "x <unknown@example.com>"),
+        ];
+        for &((name, email), expected) in cases.iter() {
+            let actual = match mailmap.lookup(name.as_bytes(), email.as_bytes()) {
+                Some((name, email)) => identity(&name, &email),
+                None => identity(name.as_bytes(), email.as_bytes()),
+            };
+            assert_eq!(actual, expected, "{} <{}>", name, email);
+        }
+
+        let signature = git2::Signature::now("x", "JOE@example.com").unwrap();
+        assert_eq!(mailmap.map_user(&signature), "Joe Developer <JOE@example.com>");
+        assert!(mailmap.lookup(b"x", b"unknown@example.com").is_none());
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let td = TempDir::new("test").unwrap();
+        let path = td.path().join(".mailmap");
+        File::create(&path).unwrap()
+            .write_all(b"# comment\nJoe <joe@example.com>\nJ\xf6rg <joerg@example.com> J\xd6RG <JOERG@old>\n").unwrap();
+
+        // latin-1 entries match latin-1 identities byte by byte, case-insensitively in ASCII
+        let mailmap = Mailmap::new(&path).unwrap().unwrap();
+        let (name, email) = mailmap.lookup(b"J\xd6RG", b"joerg@OLD").unwrap();
+        assert_eq!(name, b"J\xf6rg".to_vec());
+        assert_eq!(identity(&name, &email), "J\u{fffd}rg <joerg@example.com>");
+        assert!(mailmap.lookup(b"J\xc3\x96RG", b"joerg@old").is_none());
+
+        assert!(Mailmap::new(&td.path().join("missing")).unwrap().is_none());
+    }
+
+    #[test]
+    fn from_repo() {
+        let (td, repo) = ::test::repo_init();
+        let lookup = |mailmap: &Mailmap, email: &str| {
+            mailmap.lookup(b"x", email.as_bytes()).map(|(name, _)| String::from_utf8(name).unwrap())
+        };
+        assert!(Mailmap::from_repo(&repo).unwrap().is_none());
+
+        ::test::commit(&repo, ".mailmap", "From Blob <email>\nOnly Blob <blob@example.com>\n");
+        File::create(td.path().join(".mailmap")).unwrap().write_all(b"From Worktree <email>\n").unwrap();
+        let mailmap = Mailmap::from_repo(&repo).unwrap().unwrap();
+        assert_eq!(lookup(&mailmap, "email"), Some("From Worktree".to_string()));
+        assert_eq!(lookup(&mailmap, "blob@example.com"), None);
+
+        // the blob overrides the worktree, the file overrides both
+        repo.config().unwrap().set_str("mailmap.blob", "HEAD:.mailmap").unwrap();
+        let mailmap = Mailmap::from_repo(&repo).unwrap().unwrap();
+        assert_eq!(lookup(&mailmap, "email"), Some("From Blob".to_string()));
+        assert_eq!(lookup(&mailmap, "blob@example.com"), Some("Only Blob".to_string()));
+
+        let central = TempDir::new("central").unwrap();
+        let path = central.path().join("mailmap");
+        File::create(&path).unwrap().write_all(b"From File <email>\n").unwrap();
+        repo.config().unwrap().set_str("mailmap.file", path.to_str().unwrap()).unwrap();
+        let mailmap = Mailmap::from_repo(&repo).unwrap().unwrap();
+        assert_eq!(lookup(&mailmap, "email"), Some("From File".to_string()));
+        assert_eq!(lookup(&mailmap, "blob@example.com"), Some("Only Blob".to_string()));
+        let names: Vec<String> = sources(&repo).unwrap().into_iter().map(|source| source.name).collect();
+        assert_eq!(names, vec![".mailmap".to_string(), "HEAD:.mailmap".to_string(), path.display().to_string()]);
+
+        // missing sources are skipped, but not a tree given as the blob
+        repo.config().unwrap().set_str("mailmap.blob", "missing:.mailmap").unwrap();
+        assert!(Mailmap::from_repo(&repo).unwrap().is_some());
+        repo.config().unwrap().set_str("mailmap.blob", "HEAD^{tree}").unwrap();
+        match Mailmap::from_repo(&repo) {
+            Err(Error::Mailmap { ref name, .. }) => assert_eq!(name, "HEAD^{tree}"),
+            _ => panic!("expected mailmap error"),
+        }
+
+        // as well as config values which aren't UTF-8
+        repo.config().unwrap().remove("mailmap.blob").unwrap();
+        OpenOptions::new().append(true).open(repo.path().join("config")).unwrap()
+            .write_all(b"[mailmap]\n\tfile = caf\xe9\n").unwrap();
+        match Mailmap::from_repo(&repo) {
+            Err(Error::Encoding(_)) => {},
+            _ => panic!("expected encoding error"),
+        }
+
+        // bare repositories read HEAD:.mailmap by default
+        let bare_dir = TempDir::new("bare").unwrap();
+        let bare = git2::Repository::init_bare(bare_dir.path()).unwrap();
+        assert!(Mailmap::from_repo(&bare).unwrap().is_none());
+        {
+            let blob = bare.blob(b"From Blob <email>\n").unwrap();
+            let mut builder = bare.treebuilder(None).unwrap();
+            builder.insert(".mailmap", blob, 0o100644).unwrap();
+            let tree = bare.find_tree(builder.write().unwrap()).unwrap();
+            let sig = repo.signature().unwrap();
+            bare.commit(Some("HEAD"), &sig, &sig, "mailmap", &tree, &[]).unwrap();
+        }
+        let mailmap = Mailmap::from_repo(&bare).unwrap().unwrap();
+        assert_eq!(lookup(&mailmap, "email"), Some("From Blob".to_string()));
+    }
+}
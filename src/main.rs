@@ -1,150 +1,359 @@
 extern crate git2;
 extern crate chrono;
+extern crate gitostat;
 #[macro_use]
 extern crate serde_derive;
 extern crate docopt;
-extern crate core;
 extern crate regex;
-#[macro_use]
-extern crate prettytable;
-#[cfg(test)] extern crate tempdir;
 
+use std::path::Path;
+use std::process;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use chrono::{DateTime, FixedOffset};
 use docopt::Docopt;
-
-mod snapshot;
-mod heatmap;
-mod mailmap;
-mod personal;
-#[cfg(test)] mod test;
+use regex::Regex;
+use gitostat::{date, lint, mailmap, suggest, CacheMode, ColorChoice, Error, Role, Timezone, Mailmap, Merges, Options, Pathspec, Revisions, Style, Window};
 
 #[derive(Debug, Deserialize)]
 pub struct Args {
-    arg_path: String
+    cmd_mailmap: bool,
+    cmd_suggest: bool,
+    cmd_lint: bool,
+    arg_path: String,
+    arg_revision: Vec<String>,
+    flag_pathspec: Vec<String>,
+    flag_all: bool,
+    flag_branches: bool,
+    flag_tags: bool,
+    flag_since: Option<String>,
+    flag_until: Option<String>,
+    flag_merges: bool,
+    flag_first_parent: bool,
+    flag_format: Format,
+    flag_output: Option<String>,
+    flag_strict: bool,
+    flag_jobs: usize,
+    flag_author: Option<String>,
+    flag_heatmaps: bool,
+    flag_calendar: bool,
+    flag_timezone: String,
+    flag_committer: bool,
+    flag_no_cache: bool,
+    flag_rebuild_cache: bool,
+    flag_color: String,
+    flag_diff: bool,
 }
 
-#[cfg(not(test))]
-fn main() {
-    const USAGE: &'static str = "
-usage: gitostat [options] <path>
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+    Html,
+}
+
+const USAGE: &'static str = "
+usage: gitostat mailmap suggest [--diff] [options] <path> [<revision>...]
+       gitostat mailmap lint [options] <path> [<revision>...]
+       gitostat [options] [-p <pathspec>]... <path> [<revision>...]
+       gitostat (-h | --help)
+
+Revisions are given as in git log: <rev>, ^<rev>, <rev1>..<rev2>, <rev1>...<rev2>.
+HEAD is used when nothing else is selected.
+
+mailmap suggest prints .mailmap lines merging identities of the same person,
+the clusters are a guess to review before use. mailmap lint checks .mailmap,
+mailmap.blob and mailmap.file against the history and exits with 1 if there
+are problems.
+
 Options:
--h, --help show this message
-";
-    let args: Args = Docopt::new(USAGE)
-        .and_then(|d| d.deserialize())
-        .unwrap_or_else(|e| e.exit());
+  --all           walk all refs in refs/ along with HEAD
+  --branches      walk all branches
+  --tags          walk all tags
+  --since=<date>  count only commits more recent than a specific date
+  --until=<date>  count only commits older than a specific date
+  --committer     use committer dates and identities instead of the authors' ones
+  --merges        count merge commits too, diffed against their first parent
+  --first-parent  follow only the first parent of merge commits
+  --author=<pattern>
+                  count only commits of matching authors in heatmaps, a regex on the mailmapped name
+  --heatmaps      print the heatmap of every author side by side
+  --calendar      print the calendar of commits by days for every year
+  --timezone=<tz>  timezone of heatmaps: author (each commit in its own offset),
+                  utc, local or a fixed offset like +05:30, named zones aren't
+                  supported [default: author]
+  -p <pathspec>, --pathspec=<pathspec>
+                  count only files matching the pathspec, may be repeated
+  --format=<format>  output format: text, json, csv or html [default: text]
+  --color=<when>  colour the text report: auto (when stdout is a terminal
+                  and NO_COLOR isn't set), always or never [default: auto]
+  -o <path>, --output=<path>
+                  write the report into a file instead of stdout,
+                  for csv it is a directory with a file per report
+  -j <n>, --jobs=<n>
+                  number of threads for blaming files [default: 1]
+  --no-cache      neither read nor update the cache in .git/gitostat/
+  --rebuild-cache  discard the cache and compute everything anew
+  --strict        fail on commits which can't be read instead of skipping them
+  --diff          print the suggested lines as a diff against .mailmap
+  -h, --help      show this message
 
-    match gitostat::run(&args) {
-        Ok(()) => {},
-        Err(e) => println!("error: {}", e)
-    }
-}
+Dates are either absolute (2015-06-28, 2015-06-28 13:17:20) or relative (3 months ago, yesterday).
+
+Exit codes: 2 invalid options, 3 git error, 4 i/o error, 5 unreadable mailmap,
+6 invalid encoding; 1 only when mailmap lint finds problems.
+";
 
-macro_rules! error(
-    ($($arg:tt)*) => (
-        use std::io::Write;
-        match writeln!(&mut ::std::io::stderr(), $($arg)* ) {
-            Ok(_) => {},
-            Err(x) => panic!("Unable to write to stderr: {}", x),
+fn main() {
+    let args: Args = match Docopt::new(USAGE).and_then(|d| d.deserialize()) {
+        Ok(args) => args,
+        Err(e) => {
+            if e.fatal() {
+                writeln!(&mut io::stderr(), "{}", e).unwrap();
+            } else {
+                println!("{}", e);
+            }
+            process::exit(usage_exit_code(&e));
         }
-    )
-);
-
-#[macro_export]
-/// converts errors into None and output them into stderr.
-macro_rules! otry {
-    ($e:expr) => (match $e {
-        Ok(e) => e,
+    };
+
+    match run(&args) {
+        Ok(Outcome::Done) => {},
+        Ok(outcome) => process::exit(outcome.exit_code()),
         Err(e) => {
-            error!("ERROR!: {:?} {} {}", e, file!(), line!());
-            return None
+            writeln!(&mut io::stderr(), "error: {}", e).unwrap();
+            process::exit(e.exit_code());
         }
-    })
+    }
 }
 
-mod gitostat {
-    use git2;
-    use std::cmp;
-    use std::path::Path;
-    use std::collections::BTreeMap;
-    use Args;
+/// How a run which didn't fail ended.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Done,
+    /// `mailmap lint` found problems.
+    LintProblems,
+}
 
-    use snapshot::HasSnapshot;
-    use heatmap::Heatmap;
-    use mailmap::Mailmap;
-    use personal::PersonalStats;
+impl Outcome {
+    /// Exit code of the process, distinct from the ones of errors and of invalid arguments.
+    fn exit_code(&self) -> i32 {
+        match *self {
+            Outcome::Done => 0,
+            Outcome::LintProblems => 1,
+        }
+    }
+}
 
-    pub fn run(args: &Args) -> Result<(), git2::Error> {
-        let path = Path::new(&args.arg_path);
-        let repo = git2::Repository::open(path)?;
+/// Exit code for arguments docopt doesn't accept, 0 for `--help`.
+fn usage_exit_code(err: &docopt::Error) -> i32 {
+    if err.fatal() {
+        Error::Config(err.to_string()).exit_code()
+    } else {
+        0
+    }
+}
 
-        let mailmap = Mailmap::new(&path.join(".mailmap"));
+fn run(args: &Args) -> Result<Outcome, Error> {
+    let path = Path::new(&args.arg_path);
+    let repo = git2::Repository::open(path)?;
 
-        self::info(&repo, mailmap.as_ref())
+    if args.cmd_lint {
+        return Ok(if mailmap_lint(&repo, args)? { Outcome::Done } else { Outcome::LintProblems });
+    }
+    if args.cmd_suggest {
+        mailmap_suggest(&repo, args)?;
+        return Ok(Outcome::Done);
     }
 
-    fn info(repo: &git2::Repository, mailmap: Option<&Mailmap>) -> Result<(), git2::Error> {
-        let mut revwalk = repo.revwalk()?;
-        revwalk.push_head()?;
-        revwalk.set_sorting(git2::SORT_TOPOLOGICAL);
+    let mut options = Options::new();
+    options.mailmap = Mailmap::from_repo(&repo)?;
+    options.strict = args.flag_strict;
+    if args.flag_jobs == 0 {
+        return Err(Error::Config("--jobs must be at least 1".to_string()));
+    }
+    options.jobs = args.flag_jobs;
+    options.cache = if args.flag_no_cache {
+        CacheMode::Disabled
+    } else if args.flag_rebuild_cache {
+        CacheMode::Rebuild
+    } else {
+        CacheMode::Enabled
+    };
 
-        let commits: Vec<git2::Commit> = revwalk.filter_map(|oid| {
-            // trying lookup commit in repo, skip if any error
-            let commit = otry!(repo.find_commit(otry!(oid)));
-            // also skip merge-commits
-            if commit.parents().len() > 1 { return None; }
+    options.revisions = revisions(args);
 
-            Some(commit)
-        }).collect();
+    options.merges = if args.flag_first_parent {
+        Merges::FirstParent
+    } else if args.flag_merges {
+        Merges::Include
+    } else {
+        Merges::Exclude
+    };
 
-        let mut heatmap = Heatmap::new();
-        let mut authors = PersonalStats::new(&repo);
-        let mut num_files: BTreeMap<String, usize> = BTreeMap::new();
+    options.author = match args.flag_author {
+        Some(ref pattern) => Some(Regex::new(pattern)
+            .map_err(|e| Error::Config(format!("invalid --author: {}", e)))?),
+        None => None,
+    };
 
-        for (i, commit) in commits.iter().enumerate() {
+    options.role = if args.flag_committer { Role::Committer } else { Role::Author };
+    options.timezone = Timezone::parse(&args.flag_timezone)
+        .ok_or_else(|| Error::Config(format!("invalid timezone: {}", args.flag_timezone)))?;
 
-            print!("[{}/{}]\r", i+1, commits.len());
+    let color: ColorChoice = args.flag_color.parse().map_err(Error::Config)?;
+    options.style = Style::detect(color);
 
-            heatmap.append(&commit.author().when());
-            authors.append(&commit, mailmap)?;
+    options.window = Window::new(parse_date(&args.flag_since)?, parse_date(&args.flag_until)?);
+    options.pathspec = Pathspec::new(&args.flag_pathspec)?;
 
-            let files = repo.snapshot(&commit, false)?;
-            let key = format!("{}", files.datetime.format("%Y-%W"));
-            let number = num_files.entry(key).or_insert(0);
-            *number = cmp::max(*number, files.len());
-        }
-        println!("");
+    let report = gitostat::analyse(&repo, &options)?;
+    let output = args.flag_output.as_ref().map(Path::new);
 
-        if let Some(commit) = commits.first() {
-            // skip binary files because they don't counted in diffs
-            let files = repo.snapshot(commit, true)?;
-            authors.blame(&files, mailmap)?;
-            println!("Scaned {}", files.len());
+    match args.flag_format {
+        Format::Text => {
+            println!("{}", report);
+            if args.flag_heatmaps {
+                print!("Heatmaps by authors:\n{}", report.side_by_side(3));
+            }
+            if args.flag_calendar {
+                print!("{}", report.calendar);
+            }
+        },
+        Format::Json => write_output(output, &(report.to_json()? + "\n"))?,
+        Format::Csv => write_tables(output, &report.to_csv())?,
+        Format::Html => write_output(output, &report.to_html())?,
+    }
+
+    Ok(Outcome::Done)
+}
+
+/// Prints mailmap lines for the likely duplicate identities, or a diff adding them to the worktree `.mailmap`.
+fn mailmap_suggest(repo: &git2::Repository, args: &Args) -> Result<(), Error> {
+    let mailmap = Mailmap::from_repo(repo)?;
+    let lines = suggest::render(&suggest::suggest(repo, &revisions(args), mailmap.as_ref())?);
+    if !args.flag_diff {
+        return Ok(io::stdout().write_all(&lines)?);
+    }
+    if lines.is_empty() {
+        return Ok(());
+    }
+
+    let existing = read_worktree_mailmap(repo)?;
+    Ok(io::stdout().write_all(&suggest::diff(existing.as_ref().map(|content| &content[..]), &lines))?)
+}
+
+/// Prints problems of the mailmap sources git reads, returns whether there are none.
+fn mailmap_lint(repo: &git2::Repository, args: &Args) -> Result<bool, Error> {
+    let sources = mailmap::sources(repo)?;
+    let problems = lint::lint(repo, &revisions(args), &sources)?;
+    for problem in &problems {
+        match problem.location {
+            Some(_) => println!("{}", problem),
+            None => println!("mailmap: {}", problem),
         }
+    }
+    Ok(problems.is_empty())
+}
+
+/// Content of `.mailmap` in the worktree, `None` if there is no such file.
+fn read_worktree_mailmap(repo: &git2::Repository) -> Result<Option<Vec<u8>>, Error> {
+    let path = match repo.workdir() {
+        Some(workdir) => workdir.join(".mailmap"),
+        None => return Err(Error::Config("the repository has no worktree with .mailmap".to_string())),
+    };
+    match File::open(&path) {
+        Ok(mut file) => {
+            let mut content = Vec::new();
+            file.read_to_end(&mut content)?;
+            Ok(Some(content))
+        },
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(Error::from(err)),
+    }
+}
 
-        let mut vec: Vec<usize> = num_files.values().cloned().collect();
-        vec.sort_by(|a, b| b.cmp(a));
-        let max = cmp::max(1, vec[0]);
+fn revisions(args: &Args) -> Revisions {
+    let mut revisions = Revisions::new(&args.arg_revision);
+    revisions.all(args.flag_all)
+        .branches(args.flag_branches)
+        .tags(args.flag_tags);
+    revisions
+}
 
-        const WIDTH: usize = 60;
+fn parse_date(input: &Option<String>) -> Result<Option<DateTime<FixedOffset>>, Error> {
+    match *input {
+        None => Ok(None),
+        Some(ref input) => date::parse(input)
+            .map(Some)
+            .ok_or_else(|| Error::Config(format!("invalid date: {}", input)))
+    }
+}
 
-        let coeff = if max > WIDTH {
-            WIDTH as f32 / max as f32
-        } else {
-            1f32
-        };
+/// Writes content into the file or into stdout when there is no path.
+fn write_output(path: Option<&Path>, content: &str) -> io::Result<()> {
+    match path {
+        Some(path) => File::create(path)?.write_all(content.as_bytes()),
+        None => io::stdout().write_all(content.as_bytes()),
+    }
+}
 
-        println!("Files in repo:");
-        for (key, &val) in &num_files {
-            let value = (val as f32 * coeff).round() as usize;
-            let bar = (0..value).map(|_| "░").collect::<String>();
-            println!("{} {:3} {}", key, val, bar + "▏");
+/// Writes each table into `<dir>/<name>.csv`, or into stdout one after another
+/// with `# <name>` line before and an empty line after each table.
+fn write_tables(dir: Option<&Path>, tables: &[(&str, String)]) -> io::Result<()> {
+    match dir {
+        Some(dir) => {
+            fs::create_dir_all(dir)?;
+            for &(name, ref content) in tables {
+                write_output(Some(&dir.join(format!("{}.csv", name))), content)?;
+            }
+        },
+        None => {
+            for &(name, ref content) in tables {
+                write_output(None, &format!("# {}\n{}\n", name, content))?;
+            }
         }
-        println!("");
+    }
 
-        println!("{}", heatmap);
-        println!("{}", authors);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use docopt::Docopt;
+    use gitostat::Error;
+    use {Args, Outcome, USAGE, usage_exit_code};
+
+    fn exit_code(argv: &[&str]) -> i32 {
+        match Docopt::new(USAGE).and_then(|d| d.argv(argv.iter()).deserialize::<Args>()) {
+            Ok(_) => 0,
+            Err(e) => usage_exit_code(&e),
+        }
+    }
 
-        Ok(())
+    #[test]
+    fn usage_exit_codes() {
+        assert_eq!(exit_code(&["gitostat", "."]), 0);
+        assert_eq!(exit_code(&["gitostat", "--help"]), 0);
+        assert_eq!(exit_code(&["gitostat", "--bogus", "."]), 2);
+        assert_eq!(exit_code(&["gitostat"]), 2);
+        assert_eq!(exit_code(&["gitostat", "--format=yaml", "."]), 2);
     }
 
+    #[test]
+    fn exit_codes_are_distinct() {
+        let mut codes = vec![
+            Outcome::LintProblems.exit_code(),
+            Error::Config(String::new()).exit_code(),
+            Error::Io(::std::io::Error::new(::std::io::ErrorKind::Other, "")).exit_code(),
+            Error::Git(::git2::Error::from_str("")).exit_code(),
+            Error::Mailmap { name: String::new(), message: String::new() }.exit_code(),
+            Error::Encoding(String::new()).exit_code(),
+            exit_code(&["gitostat", "--bogus", "."]),
+        ];
+        codes.sort();
+        assert_eq!(codes, vec![1, 2, 2, 3, 4, 5, 6]);
+        assert_eq!(Outcome::Done.exit_code(), 0);
+    }
 }
@@ -0,0 +1,74 @@
+use git2;
+use std::path::Path;
+
+/// Restricts reports to the files matching any of given git pathspecs.
+pub struct Pathspec {
+    specs: Vec<String>,
+    inner: git2::Pathspec,
+}
+
+impl Pathspec {
+    /// Returns `None` for the empty list, meaning that all files are matched.
+    pub fn new(specs: &[String]) -> Result<Option<Pathspec>, git2::Error> {
+        if specs.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(Pathspec {
+            specs: specs.to_vec(),
+            inner: git2::Pathspec::new(specs.iter())?,
+        }))
+    }
+
+    pub fn specs(&self) -> &[String] {
+        &self.specs
+    }
+
+    pub fn matches(&self, path: &Path) -> bool {
+        self.inner.matches_path(path, git2::PATHSPEC_DEFAULT)
+    }
+
+    /// Diff options limited to the matched files.
+    pub fn diff_options(&self) -> git2::DiffOptions {
+        let mut opts = git2::DiffOptions::new();
+        for spec in &self.specs {
+            opts.pathspec(spec);
+        }
+
+        opts
+    }
+
+    /// Checks whether commit changes any of the matched files in comparison with its first parent.
+    pub fn touches(&self, repo: &git2::Repository, commit: &git2::Commit) -> Result<bool, git2::Error> {
+        let tree = commit.tree()?;
+        let ptree = match commit.parents().len() {
+            0 => None,
+            _ => Some(commit.parent(0)?.tree()?),
+        };
+
+        let diff = repo.diff_tree_to_tree(ptree.as_ref(), Some(&tree), Some(&mut self.diff_options()))?;
+        Ok(diff.deltas().len() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use pathspec::Pathspec;
+
+    #[test]
+    fn smoke() {
+        let (_td, repo) = ::test::repo_init();
+        let foo = ::test::commit(&repo, "services/billing/foo", "foo");
+        let bar = ::test::commit(&repo, "services/auth/bar", "bar");
+
+        let pathspec = Pathspec::new(&["services/billing".to_string()]).unwrap().unwrap();
+        assert!(pathspec.matches(Path::new("services/billing/foo")));
+        assert!(!pathspec.matches(Path::new("services/auth/bar")));
+
+        assert!(pathspec.touches(&repo, &repo.find_commit(foo).unwrap()).unwrap());
+        assert!(!pathspec.touches(&repo, &repo.find_commit(bar).unwrap()).unwrap());
+
+        assert!(Pathspec::new(&[]).unwrap().is_none());
+    }
+}
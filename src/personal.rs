@@ -1,48 +1,135 @@
-use std::{fmt,ops,cmp};
-use std::error::Error;
+use std::{fmt,ops,cmp,thread};
 use std::ops::{Add, AddAssign};
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, hash_map};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use git2;
 use chrono;
-use chrono::offset::{FixedOffset, Utc, Local, TimeZone};
-use mailmap::Mailmap;
+use chrono::offset::{FixedOffset, Local, Offset};
+use mailmap::{Identity, Mailmap};
 use snapshot::Snapshot;
+use date::Window;
+use pathspec::Pathspec;
+use error::Error;
+use cache::Cache;
+use role::Role;
+use style::Style;
 use prettytable::{Table, format};
 
 pub struct PersonalStats<'repo> {
     repo: &'repo git2::Repository,
-    authors: HashMap<String, Stat>,
+    /// Keyed by the identity after mailmap, as bytes, it is decoded only for display.
+    authors: HashMap<Identity, Stat>,
+    window: Window,
+    pathspec: Option<&'repo Pathspec>,
+    jobs: usize,
+    cache: Cache,
+    role: Role,
+    style: Style,
 }
 
 impl<'repo> PersonalStats<'repo> {
     pub fn new(repo: &'repo git2::Repository) -> PersonalStats<'repo> {
-        PersonalStats { repo: repo, authors: HashMap::new() }
+        PersonalStats { repo: repo, authors: HashMap::new(), window: Window::default(), pathspec: None, jobs: 1,
+                        cache: Cache::new(), role: Role::Author, style: Style::plain() }
     }
 
-    pub fn append(&mut self, commit: &git2::Commit, mailmap: Option<&Mailmap>) -> Result<(), git2::Error> {
-        let name = PersonalStats::mapped_name(&commit.author(), mailmap)?;
+    /// Number of threads used by `blame`, each of them opens its own repository handle.
+    pub fn set_jobs(&mut self, jobs: usize) -> &mut PersonalStats<'repo> {
+        self.jobs = cmp::max(1, jobs);
+        self
+    }
 
-        let stat = self.repo.stat(&commit)?;
+    /// Limits the activity chart to the given time window.
+    pub fn set_window(&mut self, window: Window) -> &mut PersonalStats<'repo> {
+        self.window = window;
+        self
+    }
+
+    /// Counts insertions and deletions only in the matched files.
+    pub fn set_pathspec(&mut self, pathspec: Option<&'repo Pathspec>) -> &mut PersonalStats<'repo> {
+        self.pathspec = pathspec;
+        self
+    }
+
+    /// Stats and blame results are taken from the cache when possible and stored into it otherwise.
+    pub fn set_cache(&mut self, cache: Cache) -> &mut PersonalStats<'repo> {
+        self.cache = cache;
+        self
+    }
+
+    pub fn cache(&self) -> &Cache {
+        &self.cache
+    }
+
+    /// How the weekly activity and the table are drawn.
+    pub fn set_style(&mut self, style: Style) -> &mut PersonalStats<'repo> {
+        self.style = style;
+        self
+    }
+
+    /// Whether commits are attributed to authors at their author dates or to committers at commit dates.
+    pub fn set_role(&mut self, role: Role) -> &mut PersonalStats<'repo> {
+        self.role = role;
+        self
+    }
+
+    pub fn append(&mut self, commit: &git2::Commit, mailmap: Option<&Mailmap>) -> Result<(), Error> {
+        let name = PersonalStats::mapped_identity(&self.role.signature(commit), mailmap);
+
+        let (insertions, deletions) = match self.cache.stat(commit.id(), self.pathspec) {
+            Some(changes) => changes,
+            None => {
+                let stat = self.repo.stat(&commit, self.pathspec, self.role)?;
+                self.cache.insert_stat(commit.id(), self.pathspec, (stat.insertions, stat.deletions));
+                (stat.insertions, stat.deletions)
+            }
+        };
+        let stat = Stat::commit(commit, self.role, insertions, deletions);
 
         *self.authors.entry(name).or_insert(Stat::new()) += stat;
         Ok(())
     }
 
-    pub fn blame(&mut self, files: &Snapshot, mailmap: Option<&Mailmap>) -> Result<(), git2::Error> {
-        let mut opts = git2::BlameOptions::new();
-        opts.track_copies_same_commit_moves(true)
-            .track_copies_same_commit_copies(true);
-
-        for (i, path) in files.iter().enumerate() {
-            print!("[{}/{}]\r", i+1, files.len());
+    /// Counts lines owned by each author in the files of the snapshot.
+    /// The result doesn't depend on the number of jobs.
+    pub fn blame(&mut self, files: &Snapshot, mailmap: Option<&Mailmap>) -> Result<(), Error> {
+        self.cache.set_head(self.repo, files.id)?;
+
+        let missing: Vec<(PathBuf, git2::Oid)> = files.blobs()
+            .filter(|&(path, &blob)| self.cache.blame(blob, path).is_none())
+            .map(|(path, &blob)| (path.clone(), blob))
+            .collect();
+        let paths: Vec<PathBuf> = missing.iter().map(|&(ref path, _)| path.clone()).collect();
+
+        let blamed = if self.jobs == 1 || paths.len() < 2 {
+            let mut blamed = Vec::new();
+            for (i, path) in paths.iter().enumerate() {
+                progress!("[{}/{}]\r", i+1, paths.len());
+                blamed.push(blame_file(self.repo, files.id, path)?);
+            }
+            blamed
+        } else {
+            self.blame_parallel(paths, files.id)?
+        };
 
-            let blame = self.repo.blame_file(path, Some(&mut opts))?;
+        for ((path, blob), lines) in missing.into_iter().zip(blamed.into_iter()) {
+            self.cache.insert_blame(blob, &path, lines);
+        }
 
-            for hunk in blame.iter() {
-                let name = PersonalStats::mapped_name(&hunk.final_signature(), mailmap)?;
+        // commits are mapped to authors here, so the cache doesn't depend on the mailmap
+        let mut names: HashMap<String, Identity> = HashMap::new();
+        for (path, &blob) in files.blobs() {
+            let lines = self.cache.blame(blob, path).expect("file wasn't blamed");
+            for (id, &num) in lines {
+                if !names.contains_key(id) {
+                    let commit = self.repo.find_commit(git2::Oid::from_str(id)?)?;
+                    names.insert(id.clone(), PersonalStats::mapped_identity(&self.role.signature(&commit), mailmap));
+                }
 
-                if let Some(entry) = self.authors.get_mut(&name) {
-                    entry.num_lines += hunk.lines_in_hunk();
+                if let Some(entry) = self.authors.get_mut(&names[id]) {
+                    entry.num_lines += num;
                 }
             }
         }
@@ -50,72 +137,157 @@ impl<'repo> PersonalStats<'repo> {
         Ok(())
     }
 
-    pub fn mapped_name(sig: &git2::Signature, mailmap: Option<&Mailmap>) -> Result<String, git2::Error> {
+    /// Blames files in a pool of threads, returns lines by commit for each file in the same order.
+    fn blame_parallel(&self, paths: Vec<PathBuf>, id: git2::Oid) -> Result<Vec<HashMap<String, usize>>, Error> {
+        let num = paths.len();
+        let paths = Arc::new(paths);
+        let next = Arc::new(AtomicUsize::new(0));
+        let (tx, rx) = mpsc::channel();
+
+        let mut workers = Vec::new();
+        for _ in 0..cmp::min(self.jobs, num) {
+            let repo = git2::Repository::open(self.repo.path())?;
+            let (paths, next, tx) = (paths.clone(), next.clone(), tx.clone());
+
+            workers.push(thread::spawn(move || {
+                loop {
+                    let i = next.fetch_add(1, Ordering::SeqCst);
+                    if i >= paths.len() { break; }
+
+                    let result = blame_file(&repo, id, &paths[i]);
+                    if tx.send((i, result)).is_err() { break; }
+                }
+            }));
+        }
+        drop(tx);
+
+        let mut results: Vec<Option<Result<HashMap<String, usize>, Error>>> = (0..num).map(|_| None).collect();
+        for (done, (i, result)) in rx.iter().enumerate() {
+            progress!("[{}/{}]\r", done+1, num);
+            results[i] = Some(result);
+        }
+        for worker in workers {
+            worker.join().expect("blame worker panicked");
+        }
+
+        // the first error in the order of files, as in the sequential run
+        results.into_iter().map(|result| result.expect("file wasn't blamed")).collect()
+    }
+
+    /// Sum of all authors stats.
+    pub fn total(&self) -> Stat {
+        self.authors.iter().fold(Stat::new(), |total, item| total + item.1)
+    }
+
+    pub fn iter(&self) -> hash_map::Iter<Identity, Stat> {
+        self.authors.iter()
+    }
+
+    /// Number of commits per week from the first commit (or the start of the window) till now
+    /// (or the end of the window), weeks without commits are included.
+    /// Empty if there are no commits and the start of the window is not set.
+    pub fn weeks(&self) -> Vec<(String, usize)> {
+        let total = self.total();
+
+        let now = Local::now();
+        let end = self.window.until.unwrap_or_else(|| now.with_timezone(&now.offset().fix()));
+        let start = match self.window.since.or(total.first_commit()) {
+            Some(start) => start,
+            None => return Vec::new()
+        };
+        let num_weeks = end.signed_duration_since(start).num_weeks();
+
+        // the week of `end` is listed as well, even if only a part of it is in the window
+        (0..num_weeks + 1).map(|i| {
+            let step = start.add(chrono::Duration::weeks(i));
+            let key = format!("{}", step.format("%Y-%W"));
+            let val = *total.activity_weeks.get(&key).unwrap_or(&0);
+            (key, val)
+        }).collect()
+    }
+
+    /// Lines of the authors table sorted by name, the last one is the total.
+    pub fn rows(&self) -> Vec<Row> {
+        let total = self.total();
+
+        // identities are sorted first, so the ones shown with the same name keep their order
+        let mut authors: Vec<(&Identity, &Stat)> = self.authors.iter().collect();
+        authors.sort_by(|a, b| a.0.cmp(b.0));
+        let mut rows: Vec<Row> = authors.into_iter()
+            .map(|(identity, stat)| Row::new(&identity.to_string(), stat, &total))
+            .collect();
+        rows.sort_by(|a, b| a.name.cmp(&b.name));
+        rows.push(Row::new("Total", &total, &total));
+
+        rows
+    }
+
+    /// Identity of the signature after mailmap.
+    pub fn mapped_identity(sig: &git2::Signature, mailmap: Option<&Mailmap>) -> Identity {
         match mailmap {
-            None => Ok(format!("{}", sig)),
-            Some(mm) => {
-                mm.map_user(&sig)
-                     .map_err(|err| git2::Error::from_str(err.description()))
-            }
+            None => Identity::new(sig),
+            Some(mm) => mm.map(&Identity::new(sig))
         }
     }
 }
 
+/// Lines of the file at the revision by the commit which introduced them.
+fn blame_file(repo: &git2::Repository, id: git2::Oid, path: &Path) -> Result<HashMap<String, usize>, Error> {
+    let mut opts = git2::BlameOptions::new();
+    opts.track_copies_same_commit_moves(true)
+        .track_copies_same_commit_copies(true)
+        .newest_commit(id);
+
+    let blame = repo.blame_file(path, Some(&mut opts))?;
+
+    let mut result = HashMap::new();
+    for hunk in blame.iter() {
+        *result.entry(hunk.final_commit_id().to_string()).or_insert(0) += hunk.lines_in_hunk();
+    }
+
+    Ok(result)
+}
+
 impl<'repo> fmt::Display for PersonalStats<'repo> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut table = Table::new();
-        let format = format::FormatBuilder::new()
+        let format = if self.style.ascii {
+            *format::consts::FORMAT_DEFAULT
+        } else {
+            format::FormatBuilder::new()
                 .column_separator('│')
                 .borders('│')
                 .separator(format::LinePosition::Top,    format::LineSeparator::new('─', '┬', '┌', '┐'))
                 .separator(format::LinePosition::Intern, format::LineSeparator::new('─', '┼', '├', '┤'))
                 .separator(format::LinePosition::Bottom, format::LineSeparator::new('─', '┴', '└', '┘'))
                 .padding(1, 1)
-                .build();
+                .build()
+        };
 
         table.set_format(format);
         table.add_row(row!["Author", "Commits (%)", "Insertions", "Deletions", "Owned lines (%)", "Live code", "Age in days", "Active days (%)"]);
 
-        let total = self.authors.iter().fold(Stat::new(), |total, item| total + item.1);
-        let total_days = total.num_days();
-        let total_active_days = total.activity_days.len();
-        let total_active_days_percent = total_active_days as f32 / total_days as f32 * 100_f32;
-        let total_live_code_percent = total.num_lines as f32 / total.insertions as f32 * 100_f32;
-
-        for (name, stat) in &self.authors {
-            let active_days = stat.activity_days.len();
-            let all_days = cmp::max(1, stat.num_days());
-            let active_days_percent = active_days as f32 / all_days as f32 * 100_f32;
-            let commit_percent = stat.num_commit as f32 / total.num_commit as f32 * 100_f32;
-            let lines_percent = stat.num_lines as f32 / total.num_lines as f32 * 100_f32;
-            let live_code_percent = stat.num_lines as f32 / stat.insertions as f32 * 100_f32;
-
+        for row in self.rows() {
             table.add_row(row![
-                          name,
-                          format!("{} ({:.2}%)", stat.num_commit, commit_percent),
-                          format!("{}", stat.insertions),
-                          format!("{}", stat.deletions),
-                          format!("{} ({:.2}%)", stat.num_lines, lines_percent),
-                          format!("{:.2}%", live_code_percent),
-                          format!("{}", all_days),
-                          format!("{} ({:.2}%)", active_days, active_days_percent)
+                          row.name,
+                          format!("{} ({:.2}%)", row.commits, row.commits_percent),
+                          format!("{}", row.insertions),
+                          format!("{}", row.deletions),
+                          format!("{} ({:.2}%)", row.owned_lines, row.owned_lines_percent),
+                          format!("{:.2}%", row.live_code_percent),
+                          format!("{}", row.age_in_days),
+                          format!("{} ({:.2}%)", row.active_days, row.active_days_percent)
             ]);
         }
 
-        table.add_row(row![
-                      "Total",
-                      format!("{} (100%)", total.num_commit),
-                      format!("{}", total.insertions),
-                      format!("{}", total.deletions),
-                      format!("{} (100%)", total.num_lines),
-                      format!("{:.2}%", total_live_code_percent),
-                      format!("{}", total_days),
-                      format!("{} ({:.2}%)", total_active_days, total_active_days_percent)
-        ]);
-
-        let mut vec: Vec<usize> = total.activity_weeks.values().cloned().collect();
-        vec.sort_by(|a, b| b.cmp(a));
-        let max = cmp::max(1, vec[0]);
+        if self.authors.is_empty() {
+            writeln!(f, "Activity by weeks: no commits")?;
+            return writeln!(f, "Authors: no commits");
+        }
+
+        let weeks = self.weeks();
+
+        let max = cmp::max(1, weeks.iter().map(|&(_, val)| val).max().unwrap_or(0));
 
         const WIDTH: usize = 60;
 
@@ -125,18 +297,10 @@ impl<'repo> fmt::Display for PersonalStats<'repo> {
             1f32
         };
 
-        let now = Local::now();
-        let start = total.first_commit.clone().unwrap();
-        let num_weeks = now.signed_duration_since(start.datetime).num_weeks();
-
         writeln!(f, "Activity by weeks:")?;
-        for i in 0..num_weeks {
-            let step = start.datetime.add(chrono::Duration::weeks(i));
-            let key = format!("{}", step.format("%Y-%W"));
-            let val = *total.activity_weeks.get(&key).unwrap_or(&0);
+        for (key, val) in weeks {
             let value = (val as f32 * coeff).round() as usize;
-            let bar = (0..value).map(|_| "░").collect::<String>();
-            writeln!(f, "{} {:3} {}", key, val, bar + "▏")?;
+            writeln!(f, "{} {:3} {}", key, val, self.style.paint_bar(value, val, max))?;
         }
         writeln!(f, "")?;
 
@@ -144,6 +308,53 @@ impl<'repo> fmt::Display for PersonalStats<'repo> {
     }
 }
 
+/// Line of the authors table, percents are given relative to the total.
+#[derive(Debug)]
+pub struct Row {
+    pub name: String,
+    pub commits: usize,
+    pub commits_percent: f32,
+    pub insertions: usize,
+    pub deletions: usize,
+    pub owned_lines: usize,
+    pub owned_lines_percent: f32,
+    /// Percent of inserted lines which are still present.
+    pub live_code_percent: f32,
+    pub age_in_days: i64,
+    pub active_days: usize,
+    pub active_days_percent: f32,
+}
+
+impl Row {
+    fn new(name: &str, stat: &Stat, total: &Stat) -> Row {
+        let active_days = stat.activity_days.len();
+        let all_days = if stat.num_commit == 0 { 0 } else { cmp::max(1, stat.num_days()) };
+
+        Row {
+            name: name.to_string(),
+            commits: stat.num_commit,
+            commits_percent: percent(stat.num_commit, total.num_commit),
+            insertions: stat.insertions,
+            deletions: stat.deletions,
+            owned_lines: stat.num_lines,
+            owned_lines_percent: percent(stat.num_lines, total.num_lines),
+            live_code_percent: percent(stat.num_lines, stat.insertions),
+            age_in_days: all_days,
+            active_days: active_days,
+            active_days_percent: percent(active_days, all_days as usize),
+        }
+    }
+}
+
+/// Zero when there is nothing to divide by.
+fn percent(value: usize, total: usize) -> f32 {
+    if total == 0 {
+        0_f32
+    } else {
+        value as f32 / total as f32 * 100_f32
+    }
+}
+
 #[derive(Copy, Clone, Debug)]
 struct MiniCommit {
     id: git2::Oid,
@@ -151,13 +362,10 @@ struct MiniCommit {
 }
 
 impl MiniCommit {
-    pub fn new(commit: &git2::Commit) -> MiniCommit {
-        let time = commit.author().when();
-        let tz = FixedOffset::east(time.offset_minutes() * 60);
-
+    pub fn new(commit: &git2::Commit, role: Role) -> MiniCommit {
         MiniCommit {
             id: commit.id(),
-            datetime: Utc.timestamp(time.seconds(), 0).with_timezone(&tz),
+            datetime: role.datetime(commit),
         }
     }
 }
@@ -197,26 +405,50 @@ pub struct Stat {
 }
 
 pub trait HasStat {
-    fn stat(&self, commit: &git2::Commit) -> Result<Stat, git2::Error>;
+    /// Stat of the single commit, at the date of the role.
+    fn stat(&self, commit: &git2::Commit, pathspec: Option<&Pathspec>, role: Role) -> Result<Stat, git2::Error>;
 }
 
 impl HasStat for git2::Repository {
-    fn stat(&self, commit: &git2::Commit) -> Result<Stat, git2::Error> {
+    fn stat(&self, commit: &git2::Commit, pathspec: Option<&Pathspec>, role: Role) -> Result<Stat, git2::Error> {
 
-        let mini = MiniCommit::new(commit);
         let tree = commit.tree()?;
 
-        // avoid error on the initial commit
-        let ptree = if commit.parents().len() == 1 {
+        // avoid error on the initial commit, merges are diffed against the first parent
+        let ptree = if commit.parents().len() > 0 {
             let parent = commit.parent(0)?;
             parent.tree().ok()
         } else {
             None
         };
 
-        let diff = self.diff_tree_to_tree(ptree.as_ref(), Some(&tree), None)?;
+        let mut opts = pathspec.map(|pathspec| pathspec.diff_options());
+        let diff = self.diff_tree_to_tree(ptree.as_ref(), Some(&tree), opts.as_mut())?;
         let stats = diff.stats()?;
 
+        Ok(Stat::commit(commit, role, stats.insertions(), stats.deletions()))
+    }
+}
+
+impl Stat {
+    /// Create empty struct.
+    pub fn new() -> Stat {
+        Stat {
+            num_commit: 0,
+            num_lines: 0,
+            insertions: 0,
+            deletions: 0,
+            activity_days: HashMap::new(),
+            activity_weeks: BTreeMap::new(),
+            first_commit: None,
+            last_commit: None,
+        }
+    }
+
+    /// Stat of the single commit with the given changes.
+    fn commit(commit: &git2::Commit, role: Role, insertions: usize, deletions: usize) -> Stat {
+        let mini = MiniCommit::new(commit, role);
+
         let mut activity_days = HashMap::new();
         let day = format!("{}", mini.datetime.format("%Y-%m-%d"));
         activity_days.insert(day, 1);
@@ -225,41 +457,60 @@ impl HasStat for git2::Repository {
         let week = format!("{}", mini.datetime.format("%Y-%W"));
         activity_weeks.insert(week, 1);
 
-        Ok(Stat {
+        Stat {
             num_commit: 1,
             num_lines: 0,
-            insertions: stats.insertions(),
-            deletions: stats.deletions(),
+            insertions: insertions,
+            deletions: deletions,
 
             activity_days: activity_days,
             activity_weeks: activity_weeks,
             first_commit: Some(mini),
             last_commit: Some(mini),
-        })
+        }
     }
-}
 
-impl Stat {
-    /// Create empty struct.
-    pub fn new() -> Stat {
-        Stat {
-            num_commit: 0,
-            num_lines: 0,
-            insertions: 0,
-            deletions: 0,
-            activity_days: HashMap::new(),
-            activity_weeks: BTreeMap::new(),
-            first_commit: None,
-            last_commit: None,
-        }
+    pub fn num_commit(&self) -> usize {
+        self.num_commit
     }
 
-    /// Returns number of days between first and last commits.
-    pub fn num_days(&self) -> i64 {
-        let first = self.first_commit.clone().unwrap();
-        let last = self.last_commit.clone().unwrap();
+    /// Number of lines in the last walked revision written by the author.
+    pub fn num_lines(&self) -> usize {
+        self.num_lines
+    }
+
+    pub fn insertions(&self) -> usize {
+        self.insertions
+    }
+
+    pub fn deletions(&self) -> usize {
+        self.deletions
+    }
+
+    /// Number of days with at least one commit.
+    pub fn num_active_days(&self) -> usize {
+        self.activity_days.len()
+    }
+
+    /// Number of commits per week, keyed by `%Y-%W`.
+    pub fn activity_weeks(&self) -> &BTreeMap<String, usize> {
+        &self.activity_weeks
+    }
+
+    pub fn first_commit(&self) -> Option<chrono::DateTime<FixedOffset>> {
+        self.first_commit.map(|commit| commit.datetime)
+    }
+
+    pub fn last_commit(&self) -> Option<chrono::DateTime<FixedOffset>> {
+        self.last_commit.map(|commit| commit.datetime)
+    }
 
-        last.datetime.signed_duration_since(first.datetime).num_days()
+    /// Returns number of days between first and last commits, zero if there are no commits.
+    pub fn num_days(&self) -> i64 {
+        match (self.first_commit, self.last_commit) {
+            (Some(first), Some(last)) => last.datetime.signed_duration_since(first.datetime).num_days(),
+            _ => 0
+        }
     }
 }
 
@@ -322,3 +573,50 @@ impl AddAssign for Stat {
         self.last_commit = cmp::max(self.last_commit, rhs.last_commit);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use git2;
+    use chrono::DateTime;
+    use date::Window;
+    use role::Role;
+    use personal::{HasStat, PersonalStats};
+
+    #[test]
+    fn stat_role() {
+        let (_td, repo) = ::test::repo_init();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let head = repo.find_commit(repo.refname_to_id("HEAD").unwrap()).unwrap();
+        let author = git2::Signature::new("author", "a@example.com", &git2::Time::new(946684800, 0)).unwrap();
+        let committer = git2::Signature::new("committer", "c@example.com", &git2::Time::new(1435475840, 0)).unwrap();
+        let id = repo.commit(Some("HEAD"), &author, &committer, "picked", &tree, &[&head]).unwrap();
+        let commit = repo.find_commit(id).unwrap();
+
+        let stat = repo.stat(&commit, None, Role::Author).unwrap();
+        assert_eq!(stat.first_commit().unwrap().to_rfc3339(), "2000-01-01T00:00:00+00:00");
+        assert_eq!(stat.activity_weeks().keys().collect::<Vec<_>>(), vec!["2000-00"]);
+
+        let stat = repo.stat(&commit, None, Role::Committer).unwrap();
+        assert_eq!(stat.first_commit().unwrap().to_rfc3339(), "2015-06-28T07:17:20+00:00");
+        assert_eq!(stat.activity_weeks().keys().collect::<Vec<_>>(), vec!["2015-25"]);
+    }
+
+    #[test]
+    fn weeks() {
+        let (_td, repo) = ::test::repo_init();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let head = repo.find_commit(repo.refname_to_id("HEAD").unwrap()).unwrap();
+        // Saturday, in the second week of 2000
+        let sig = git2::Signature::new("name", "email", &git2::Time::new(947332800, 0)).unwrap();
+        let id = repo.commit(Some("HEAD"), &sig, &sig, "late", &tree, &[&head]).unwrap();
+
+        let mut authors = PersonalStats::new(&repo);
+        let since = DateTime::parse_from_rfc3339("2000-01-01T00:00:00+00:00").unwrap();
+        let until = DateTime::parse_from_rfc3339("2000-01-08T23:00:00+00:00").unwrap();
+        authors.set_window(Window::new(Some(since), Some(until)));
+        authors.append(&repo.find_commit(id).unwrap(), None).unwrap();
+
+        // the last week is only partly in the window, but its commits are counted
+        assert_eq!(authors.weeks(), vec![("2000-00".to_string(), 0), ("2000-01".to_string(), 1)]);
+    }
+}
@@ -0,0 +1,599 @@
+use git2;
+use regex::Regex;
+use std::{cmp, fmt};
+use std::collections::{BTreeMap, HashMap};
+
+use snapshot::{HasSnapshot, Snapshot};
+use role::Role;
+use heatmap::{Heatmap, SideBySide, Timezone};
+use calendar::Calendar;
+use mailmap::{Identity, Mailmap};
+use personal::PersonalStats;
+use revision::Revisions;
+use date::Window;
+use filetype::{self, Breakdown};
+use loc::LineCounter;
+use pathspec::Pathspec;
+use json;
+use csv;
+use html;
+use cache::{Cache, CacheMode};
+use style::Style;
+use error::Error;
+
+/// How merge commits are treated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Merges {
+    /// Skip merge commits, only the commits brought by them are counted.
+    Exclude,
+    /// Count merge commits as well, diffed against their first parent.
+    Include,
+    /// Follow only the first parent of merges, i.e. what landed on the branch.
+    /// Merges themselves are diffed against their first parent.
+    FirstParent,
+}
+
+/// What part of the history is analysed.
+pub struct Options {
+    pub revisions: Revisions,
+    pub window: Window,
+    /// `None` means all files.
+    pub pathspec: Option<Pathspec>,
+    pub mailmap: Option<Mailmap>,
+    pub merges: Merges,
+    /// Number of threads for blaming files.
+    pub jobs: usize,
+    /// Only commits of the authors matching the pattern (after mailmap) are counted in heatmaps and the calendar.
+    pub author: Option<Regex>,
+    /// Whether dates and identities of authors or of committers are used in every report.
+    pub role: Role,
+    /// Timezone in which commits are bucketed in heatmaps.
+    pub timezone: Timezone,
+    /// Whether stats and blame results are kept in `.git/gitostat/` between runs.
+    pub cache: CacheMode,
+    /// Fail on commits which can't be read instead of skipping them.
+    pub strict: bool,
+    /// How charts and tables of the text report are drawn.
+    pub style: Style,
+}
+
+impl Options {
+    /// History of HEAD without any restrictions.
+    pub fn new() -> Options {
+        Options {
+            revisions: Revisions::new(&[]),
+            window: Window::default(),
+            pathspec: None,
+            mailmap: None,
+            merges: Merges::Exclude,
+            jobs: 1,
+            cache: CacheMode::Disabled,
+            author: None,
+            timezone: Timezone::Author,
+            role: Role::Author,
+            strict: false,
+            style: Style::plain(),
+        }
+    }
+}
+
+/// Result of the analysis.
+pub struct Report<'repo> {
+    /// Commits by the hour of the week.
+    pub heatmap: Heatmap,
+    /// Commits by the hour of the week of each author, by the identity after mailmap.
+    pub author_heatmaps: BTreeMap<Identity, Heatmap>,
+    /// Commits by the day, of the same authors as in `heatmap`.
+    pub calendar: Calendar,
+    /// Stats of each author.
+    pub authors: PersonalStats<'repo>,
+    /// Max number of files per week, keyed by `%Y-%W`.
+    pub files: BTreeMap<String, usize>,
+    /// Number of files of each type in the largest snapshot of the week, keyed by `%Y-%W`.
+    pub file_types: BTreeMap<String, Breakdown>,
+    /// Lines of each file type in the newest snapshot of the week, keyed by `%Y-%W`.
+    pub lines: BTreeMap<String, Breakdown>,
+    /// Number of commits whose identity isn't valid UTF-8, such names are shown with replacement characters.
+    pub non_utf8_identities: usize,
+    /// How the text report is drawn.
+    pub style: Style,
+}
+
+/// Walks the selected commits and collects all stats. Progress is written into stderr.
+pub fn analyse<'repo>(repo: &'repo git2::Repository, options: &'repo Options) -> Result<Report<'repo>, Error> {
+    let window = options.window;
+    let pathspec = options.pathspec.as_ref();
+    let mailmap = options.mailmap.as_ref();
+    let role = options.role;
+
+    let mut revwalk = repo.revwalk()?;
+    options.revisions.push(repo, &mut revwalk)?;
+    revwalk.set_sorting(git2::SORT_TOPOLOGICAL);
+    if options.merges == Merges::FirstParent {
+        revwalk.simplify_first_parent();
+    }
+
+    let mut commits: Vec<git2::Commit> = Vec::new();
+    for oid in revwalk {
+        match select(repo, oid, options) {
+            Ok(Some(commit)) => commits.push(commit),
+            Ok(None) => {},
+            Err(err) => {
+                if options.strict { return Err(err); }
+                error!("skipping commit: {}", err);
+            }
+        }
+    }
+
+    let mut heatmap = Heatmap::with_timezone(options.timezone);
+    heatmap.set_style(options.style);
+    let mut author_heatmaps: BTreeMap<Identity, Heatmap> = BTreeMap::new();
+    let mut calendar = Calendar::with_timezone(options.timezone);
+    calendar.set_style(options.style);
+    let mut authors = PersonalStats::new(repo);
+    authors.set_window(window).set_pathspec(pathspec).set_jobs(options.jobs).set_role(role)
+        .set_cache(Cache::open(repo, options.cache)?).set_style(options.style);
+    let mut num_files: BTreeMap<String, usize> = BTreeMap::new();
+    let mut file_types: BTreeMap<String, Breakdown> = BTreeMap::new();
+    let mut lines: BTreeMap<String, Breakdown> = BTreeMap::new();
+    // date of the snapshot in `lines` of each week, in seconds
+    let mut newest: HashMap<String, i64> = HashMap::new();
+    let mut non_utf8_identities = 0;
+
+    // snapshots are computed incrementally, each one from the previous, so the oldest goes first
+    let mut snapshot: Option<Snapshot> = None;
+    let mut counter = LineCounter::new(repo);
+    for (i, commit) in commits.iter().rev().enumerate() {
+
+        progress!("[{}/{}]\r", i+1, commits.len());
+
+        let signature = role.signature(commit);
+        if signature.name().is_none() || signature.email().is_none() {
+            non_utf8_identities += 1;
+        }
+        let name = PersonalStats::mapped_identity(&signature, mailmap);
+        if options.author.as_ref().map_or(true, |author| author.is_match(&name.to_string())) {
+            heatmap.append(&role.when(commit));
+            calendar.append(&role.when(commit));
+            author_heatmaps.entry(name).or_insert_with(|| Heatmap::with_timezone(options.timezone)).append(&role.when(commit));
+        }
+        authors.append(&commit, mailmap)?;
+
+        let files = match snapshot.take() {
+            Some(mut files) => {
+                repo.advance(&mut files, commit, pathspec, role, &mut counter)?;
+                files
+            },
+            None => {
+                let mut files = repo.snapshot(commit, false, pathspec, role)?;
+                files.count_lines(&mut counter)?;
+                files
+            },
+        };
+        let key = week(commit, role);
+
+        // the topological order of the walk doesn't follow dates, e.g. of rebased commits or of several branches,
+        // so the newest snapshot of the week is the one with the latest date, the later walked of equal ones
+        let time = role.when(commit).seconds();
+        if newest.get(&key).map_or(true, |&newest| newest <= time) {
+            newest.insert(key.clone(), time);
+            lines.insert(key.clone(), files.lines().cloned().unwrap_or_default());
+        }
+
+        let number = num_files.entry(key.clone()).or_insert(0);
+        if *number < files.len() || !file_types.contains_key(&key) {
+            *number = files.len();
+            file_types.insert(key, files.types());
+        }
+
+        snapshot = Some(files);
+    }
+    progress!("\n");
+    if non_utf8_identities > 0 {
+        error!("{} commits have non-UTF-8 identities, shown with replacement characters", non_utf8_identities);
+    }
+
+    if let Some(commit) = commits.first() {
+        // skip binary files because they don't counted in diffs
+        let files = repo.snapshot(commit, true, pathspec, role)?;
+        authors.blame(&files, mailmap)?;
+        progress!("Scaned {}\n", files.len());
+    }
+    authors.cache().save();
+
+    Ok(Report {
+        heatmap: heatmap,
+        author_heatmaps: author_heatmaps,
+        calendar: calendar,
+        authors: authors,
+        files: num_files,
+        file_types: file_types,
+        lines: lines,
+        non_utf8_identities: non_utf8_identities,
+        style: options.style,
+    })
+}
+
+/// Week of the commit, `%Y-%W` in the offset of its date.
+fn week(commit: &git2::Commit, role: Role) -> String {
+    format!("{}", role.datetime(commit).format("%Y-%W"))
+}
+
+/// Looks up the commit and checks whether it should be counted.
+fn select<'repo>(repo: &'repo git2::Repository, oid: Result<git2::Oid, git2::Error>,
+                 options: &Options) -> Result<Option<git2::Commit<'repo>>, Error> {
+    let commit = repo.find_commit(oid?)?;
+    // skip merge-commits unless asked for
+    if options.merges == Merges::Exclude && commit.parents().len() > 1 { return Ok(None); }
+    // and commits outside of the time window
+    if !options.window.contains(&options.role.when(&commit)) { return Ok(None); }
+    // and commits which don't touch the requested paths
+    if let Some(ref pathspec) = options.pathspec {
+        if !pathspec.touches(repo, &commit)? { return Ok(None); }
+    }
+
+    Ok(Some(commit))
+}
+
+impl<'repo> Report<'repo> {
+    /// One JSON document, see README for the description of the fields.
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(json::render(self)?)
+    }
+
+    /// Every report as a separate CSV table, pairs of the table name and its content.
+    pub fn to_csv(&self) -> Vec<(&'static str, String)> {
+        csv::render(self)
+    }
+
+    /// Heatmaps of all authors next to each other, `columns` in a row.
+    pub fn side_by_side(&self, columns: usize) -> SideBySide {
+        SideBySide {
+            heatmaps: self.author_heatmaps.iter().map(|(name, heatmap)| (name.to_string(), heatmap)).collect(),
+            columns: columns,
+            style: self.style,
+        }
+    }
+
+    /// Self-contained HTML page.
+    pub fn to_html(&self) -> String {
+        html::render(self)
+    }
+}
+
+impl<'repo> fmt::Display for Report<'repo> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.files.is_empty() {
+            writeln!(f, "Files in repo: no commits")?;
+            writeln!(f, "")?;
+        } else {
+            self.fmt_files(f)?;
+        }
+
+        if !self.file_types.is_empty() {
+            self.fmt_file_types(f)?;
+        }
+
+        if !self.lines.is_empty() {
+            self.fmt_lines(f)?;
+        }
+
+        writeln!(f, "{}", self.heatmap)?;
+        write!(f, "{}", self.authors)
+    }
+}
+
+impl<'repo> Report<'repo> {
+    fn fmt_files(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let max = cmp::max(1, self.files.values().cloned().max().unwrap_or(0));
+
+        const WIDTH: usize = 60;
+
+        let coeff = if max > WIDTH {
+            WIDTH as f32 / max as f32
+        } else {
+            1f32
+        };
+
+        writeln!(f, "Files in repo:")?;
+        for (key, &val) in &self.files {
+            let value = (val as f32 * coeff).round() as usize;
+            writeln!(f, "{} {:3} {}", key, val, self.style.paint_bar(value, val, max))?;
+        }
+        writeln!(f, "")
+    }
+
+    fn fmt_lines(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const WIDTH: usize = 40;
+
+        let totals: Vec<usize> = self.lines.values().map(|types| types.values().sum()).collect();
+        let max = cmp::max(1, totals.iter().cloned().max().unwrap_or(0));
+        let width = format!("{}", max).len();
+
+        writeln!(f, "Lines of code:")?;
+        for ((key, types), &total) in self.lines.iter().zip(totals.iter()) {
+            let value = (total as f32 * WIDTH as f32 / max as f32).round() as usize;
+            // padded by hand, escape codes of colours have no width
+            let bar = self.style.paint_bar(value, total, max) + &" ".repeat(WIDTH - value);
+            let languages: Vec<String> = filetype::ranked(types).iter()
+                .filter(|&&(_, num)| num > 0)
+                .map(|&(name, num)| format!("{} {}", name, num))
+                .collect();
+            writeln!(f, "{} {:>width$} {} {}", key, total, bar, languages.join(", "), width = width)?;
+        }
+        writeln!(f, "")
+    }
+
+    fn fmt_file_types(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const WIDTH: usize = 40;
+        let shades = self.style.shades();
+        let glyphs = [shades[4], shades[3], shades[2], shades[1]];
+
+        // the largest types of the latest snapshot get their own glyph, the rest share the last one
+        let latest = self.file_types.values().last().map(filetype::ranked).unwrap_or(Vec::new());
+        let glyph = |name: &str| {
+            latest.iter().position(|&(other, _)| other == name && name != filetype::OTHER)
+                .map_or(3, |i| cmp::min(i, 3))
+        };
+
+        writeln!(f, "File types:")?;
+        let mut legend: Vec<String> = latest.iter().take(3)
+            .filter(|&&(name, _)| name != filetype::OTHER)
+            .map(|&(name, _)| format!("{} {}", self.style.paint(glyphs[glyph(name)], 4 - glyph(name)), name))
+            .collect();
+        legend.push(format!("{} rest", self.style.paint(glyphs[3], 1)));
+        writeln!(f, "{}", legend.join(", "))?;
+
+        for (key, types) in &self.file_types {
+            let total = cmp::max(1, types.values().sum::<usize>());
+            let mut ranked = filetype::ranked(types);
+            ranked.sort_by_key(|&(name, _)| glyph(name));
+
+            let mut bar = String::new();
+            let mut filled = 0;
+            let mut sum = 0;
+            for &(name, num) in &ranked {
+                sum += num;
+                let end = (sum as f32 * WIDTH as f32 / total as f32).round() as usize;
+                let run: String = (filled..end).map(|_| glyphs[glyph(name)]).collect();
+                bar.push_str(&self.style.paint(&run, 4 - glyph(name)));
+                filled = end;
+            }
+            bar.push_str(&" ".repeat(WIDTH - filled));
+
+            let shares: Vec<String> = filetype::ranked(types).iter()
+                .map(|&(name, num)| format!("{} {:.0}%", name, num as f32 * 100. / total as f32))
+                .collect();
+            writeln!(f, "{} {} {}", key, bar, shares.join(", "))?;
+        }
+        writeln!(f, "")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use date::Window;
+    use regex::Regex;
+    use cache::CacheMode;
+    use git2;
+    use role::Role;
+    use std::fs::File;
+    use std::io::Write;
+    use mailmap::Mailmap;
+    use style::{Colors, Style};
+    use report::{analyse, Merges, Options};
+
+    #[test]
+    fn empty_repo() {
+        let (_td, repo) = ::test::repo_init_empty();
+        let options = Options::new();
+        let report = analyse(&repo, &options).unwrap();
+
+        assert!(report.files.is_empty());
+        assert!(report.authors.weeks().is_empty());
+        assert!(format!("{}", report).contains("Authors: no commits"));
+        assert!(report.to_json().unwrap().contains("\"files\": []"));
+        assert_eq!(report.to_csv()[3].1, "week,files\n");
+        report.to_html();
+    }
+
+    #[test]
+    fn empty_window() {
+        let (_td, repo) = ::test::repo_init();
+        let mut options = Options::new();
+        let since = DateTime::parse_from_rfc3339("2000-01-01T00:00:00+00:00").unwrap();
+        let until = DateTime::parse_from_rfc3339("2000-01-31T00:00:00+00:00").unwrap();
+        options.window = Window::new(Some(since), Some(until));
+        let report = analyse(&repo, &options).unwrap();
+
+        assert!(report.files.is_empty());
+        // weeks of the window are still listed
+        assert_eq!(report.authors.weeks().len(), 5);
+        assert!(report.authors.weeks().iter().all(|&(_, val)| val == 0));
+        assert!(format!("{}", report).contains("Files in repo: no commits"));
+    }
+
+    #[test]
+    fn merges() {
+        let (_td, repo) = ::test::repo_init();
+        ::test::merge(&repo, "side", "1\n2\n", "main", "1\n");
+
+        let count = |merges| {
+            let mut options = Options::new();
+            options.merges = merges;
+            let report = analyse(&repo, &options).unwrap();
+            let total = report.authors.total();
+            (total.num_commit(), total.insertions())
+        };
+
+        // initial, side, main
+        assert_eq!(count(Merges::Exclude), (3, 3));
+        // the merge brings side lines once more
+        assert_eq!(count(Merges::Include), (4, 5));
+        // initial, main, merge
+        assert_eq!(count(Merges::FirstParent), (3, 3));
+    }
+
+    #[test]
+    fn jobs() {
+        let (_td, repo) = ::test::repo_init();
+        for i in 0..5 {
+            ::test::commit(&repo, &format!("file{}", i), &"line\n".repeat(i + 1));
+        }
+
+        let owned = |jobs| {
+            let mut options = Options::new();
+            options.jobs = jobs;
+            let report = analyse(&repo, &options).unwrap();
+            report.authors.rows().iter().map(|row| (row.name.clone(), row.owned_lines)).collect::<Vec<_>>()
+        };
+
+        let sequential = owned(1);
+        assert_eq!(sequential.last().unwrap().1, 15);
+        assert_eq!(owned(3), sequential);
+        assert_eq!(owned(16), sequential);
+    }
+
+    #[test]
+    fn cache() {
+        let (_td, repo) = ::test::repo_init();
+        ::test::commit(&repo, "a", "1\n2\n");
+        ::test::commit(&repo, "b", "1\n");
+
+        let rows = |cache| {
+            let mut options = Options::new();
+            options.cache = cache;
+            let report = analyse(&repo, &options).unwrap();
+            report.authors.rows().iter()
+                .map(|row| (row.name.clone(), row.commits, row.insertions, row.owned_lines))
+                .collect::<Vec<_>>()
+        };
+
+        let expected = rows(CacheMode::Disabled);
+        assert!(!repo.path().join("gitostat").exists());
+        assert_eq!(rows(CacheMode::Enabled), expected);
+        assert!(repo.path().join("gitostat/cache.json").exists());
+        // taken from the cache
+        assert_eq!(rows(CacheMode::Enabled), expected);
+        assert_eq!(rows(CacheMode::Rebuild), expected);
+
+        // blame results of the unchanged files are reused after new commits
+        ::test::commit(&repo, "b", "1\n2\n3\n");
+        assert_eq!(rows(CacheMode::Enabled), rows(CacheMode::Disabled));
+    }
+
+    #[test]
+    fn newest_of_week() {
+        let (_td, repo) = ::test::repo_init();
+        let at = |seconds| git2::Signature::new("name", "email", &git2::Time::new(seconds, 0)).unwrap();
+        // Monday 10:00, then a commit on top of it dated Monday 09:00
+        ::test::commit_as(&repo, "a.rs", "1\n", &at(1434967200));
+        ::test::commit_as(&repo, "b.rs", "1\n2\n", &at(1434963600));
+
+        let options = Options::new();
+        let report = analyse(&repo, &options).unwrap();
+        assert_eq!(report.lines["2015-25"].get("Rust"), Some(&1));
+        assert_eq!(report.files["2015-25"], 2);
+    }
+
+    #[test]
+    fn author_heatmaps() {
+        let (_td, repo) = ::test::repo_init();
+        ::test::commit(&repo, "a", "a");
+
+        let mut options = Options::new();
+        let report = analyse(&repo, &options).unwrap();
+        assert_eq!(report.author_heatmaps.keys().map(|name| name.to_string()).collect::<Vec<_>>(), vec!["name <email>"]);
+        assert!(format!("{}", report.side_by_side(3)).contains("\nname <email>\n"));
+
+        options.author = Some(Regex::new("^somebody").unwrap());
+        let report = analyse(&repo, &options).unwrap();
+        assert!(report.author_heatmaps.is_empty());
+        assert_eq!((0..7).map(|day| (0..24).map(|hour| report.heatmap.get(day, hour)).sum::<u32>()).sum::<u32>(), 0);
+        // the rest of the report isn't affected
+        assert_eq!(report.authors.total().num_commit(), 2);
+    }
+
+    #[test]
+    fn committer() {
+        let (_td, repo) = ::test::repo_init();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let head = repo.find_commit(repo.refname_to_id("HEAD").unwrap()).unwrap();
+        let author = git2::Signature::new("author", "a@example.com", &git2::Time::new(946684800, 0)).unwrap();
+        let committer = git2::Signature::new("committer", "c@example.com", &git2::Time::new(1435475840, 0)).unwrap();
+        repo.commit(Some("HEAD"), &author, &committer, "picked", &tree, &[&head]).unwrap();
+
+        let mut options = Options::new();
+        let since = DateTime::parse_from_rfc3339("2015-01-01T00:00:00+00:00").unwrap();
+        let until = DateTime::parse_from_rfc3339("2015-12-31T00:00:00+00:00").unwrap();
+        options.window = Window::new(Some(since), Some(until));
+
+        // authored in 2000, committed in 2015
+        assert!(analyse(&repo, &options).unwrap().author_heatmaps.is_empty());
+        options.role = Role::Committer;
+        let report = analyse(&repo, &options).unwrap();
+        assert_eq!(report.author_heatmaps.keys().map(|name| name.to_string()).collect::<Vec<_>>(),
+                   vec!["committer <c@example.com>"]);
+        assert_eq!(report.files.keys().collect::<Vec<_>>(), vec!["2015-25"]);
+    }
+
+    #[test]
+    fn style() {
+        let (_td, repo) = ::test::repo_init();
+        ::test::commit(&repo, "src/main.rs", "fn main() {\n}\n");
+
+        let mut options = Options::new();
+        options.style = Style { colors: Colors::None, ascii: true };
+        let report = analyse(&repo, &options).unwrap();
+        let text = format!("{}{}{}", report, report.side_by_side(3), report.calendar);
+        assert!(text.bytes().all(|byte| byte < 0x80));
+        assert!(!text.contains('\x1b'));
+
+        options.style = Style { colors: Colors::Ansi256, ascii: false };
+        let report = analyse(&repo, &options).unwrap();
+        let text = format!("{}", report);
+        assert!(text.contains("\x1b[38;5;46m░"));
+        assert!(text.contains("┌"));
+    }
+
+    #[test]
+    fn non_utf8_identities() {
+        let (td, repo) = ::test::repo_init();
+        // commits by authors whose names are in latin-1, on top of HEAD
+        let commit_as = |name: &[u8]| {
+            let head = repo.refname_to_id("HEAD").unwrap();
+            let tree = repo.find_commit(head).unwrap().tree_id();
+            let mut data = format!("tree {}\nparent {}\nauthor ", tree, head).into_bytes();
+            data.extend_from_slice(name);
+            data.extend_from_slice(b" <joerg@example.com> 1435475840 +0600\ncommitter name <email> 1435475840 +0600\n\nlatin-1\n");
+            let id = repo.odb().unwrap().write(git2::ObjectType::Commit, &data).unwrap();
+            let branch = repo.head().unwrap().name().unwrap().to_string();
+            repo.reference(&branch, id, true, "latin-1").unwrap();
+        };
+        // ö
+        commit_as(b"J\xf6rg");
+        File::create(td.path().join(".mailmap")).unwrap().write_all(b"J\xf6rg Proper <joerg@example.com> J\xf6rg <joerg@example.com>\n").unwrap();
+
+        let mut options = Options::new();
+        let report = analyse(&repo, &options).unwrap();
+        assert_eq!(report.non_utf8_identities, 1);
+        assert!(report.authors.iter().any(|(name, _)| name.to_string() == "J\u{fffd}rg <joerg@example.com>"));
+
+        options.mailmap = Mailmap::from_repo(&repo).unwrap();
+        let report = analyse(&repo, &options).unwrap();
+        assert!(report.authors.iter().any(|(name, _)| name.to_string() == "J\u{fffd}rg Proper <joerg@example.com>"));
+
+        // ü, shown the same as ö but another person
+        commit_as(b"J\xfcrg");
+        options.mailmap = None;
+        let report = analyse(&repo, &options).unwrap();
+        assert_eq!(report.non_utf8_identities, 2);
+        assert_eq!(report.authors.iter().count(), 3);
+        assert_eq!(report.author_heatmaps.len(), 3);
+        let rows = report.authors.rows();
+        let names: Vec<&str> = rows.iter().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, vec!["J\u{fffd}rg <joerg@example.com>", "J\u{fffd}rg <joerg@example.com>", "name <email>", "Total"]);
+        assert!(rows[..2].iter().all(|row| row.commits == 1));
+    }
+}
@@ -0,0 +1,139 @@
+use git2;
+
+/// Set of commits to walk, described in the same way as for `git rev-list`.
+pub struct Revisions {
+    specs: Vec<String>,
+    all: bool,
+    branches: bool,
+    tags: bool,
+}
+
+impl Revisions {
+    pub fn new(specs: &[String]) -> Revisions {
+        Revisions {
+            specs: specs.to_vec(),
+            all: false,
+            branches: false,
+            tags: false,
+        }
+    }
+
+    /// Walk all refs in `refs/` along with HEAD.
+    pub fn all(&mut self, all: bool) -> &mut Revisions {
+        self.all = all;
+        self
+    }
+
+    /// Walk all refs in `refs/heads`.
+    pub fn branches(&mut self, branches: bool) -> &mut Revisions {
+        self.branches = branches;
+        self
+    }
+
+    /// Walk all refs in `refs/tags`.
+    pub fn tags(&mut self, tags: bool) -> &mut Revisions {
+        self.tags = tags;
+        self
+    }
+
+    /// Pushes and hides commits in revwalk. HEAD is walked if nothing else was pushed.
+    pub fn push(&self, repo: &git2::Repository, revwalk: &mut git2::Revwalk) -> Result<(), git2::Error> {
+        let mut pushed = false;
+
+        if self.all {
+            revwalk.push_glob("*")?;
+            push_head(repo, revwalk)?;
+            pushed = true;
+        }
+        if self.branches {
+            revwalk.push_glob("heads")?;
+            pushed = true;
+        }
+        if self.tags {
+            revwalk.push_glob("tags")?;
+            pushed = true;
+        }
+
+        for spec in &self.specs {
+            // ^<rev> excludes commits reachable from <rev>
+            if spec.starts_with('^') {
+                revwalk.hide(peel(&repo.revparse_single(&spec[1..])?)?)?;
+                continue;
+            }
+
+            let revspec = repo.revparse(spec)?;
+            let mode = revspec.mode();
+
+            match (revspec.from(), revspec.to()) {
+                // <rev1>...<rev2> is a symmetric difference
+                (Some(from), Some(to)) if mode.contains(git2::REVPARSE_MERGE_BASE) => {
+                    let (from, to) = (peel(from)?, peel(to)?);
+                    revwalk.push(from)?;
+                    revwalk.push(to)?;
+                    revwalk.hide(repo.merge_base(from, to)?)?;
+                },
+                // <rev1>..<rev2>
+                (Some(from), Some(to)) => {
+                    revwalk.hide(peel(from)?)?;
+                    revwalk.push(peel(to)?)?;
+                },
+                (Some(single), None) => revwalk.push(peel(single)?)?,
+                _ => return Err(git2::Error::from_str(&format!("invalid revision: {}", spec))),
+            }
+            pushed = true;
+        }
+
+        if !pushed {
+            push_head(repo, revwalk)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Nothing is pushed if HEAD is unborn, e.g. in the freshly initialised repository.
+fn push_head(repo: &git2::Repository, revwalk: &mut git2::Revwalk) -> Result<(), git2::Error> {
+    match repo.head() {
+        Ok(_) => revwalk.push_head(),
+        Err(ref err) if err.code() == git2::ErrorCode::UnbornBranch || err.code() == git2::ErrorCode::NotFound => Ok(()),
+        Err(err) => Err(err)
+    }
+}
+
+/// Tags and other refs are resolved to the commit they point to.
+fn peel(object: &git2::Object) -> Result<git2::Oid, git2::Error> {
+    Ok(object.peel(git2::ObjectType::Commit)?.id())
+}
+
+#[cfg(test)]
+mod tests {
+    use git2;
+    use revision::Revisions;
+
+    fn walk(repo: &git2::Repository, revisions: &Revisions) -> Vec<git2::Oid> {
+        let mut revwalk = repo.revwalk().unwrap();
+        revwalk.set_sorting(git2::SORT_TOPOLOGICAL);
+        revisions.push(repo, &mut revwalk).unwrap();
+        revwalk.map(|oid| oid.unwrap()).collect()
+    }
+
+    #[test]
+    fn range() {
+        let (_td, repo) = ::test::repo_init();
+        let first = ::test::commit(&repo, "foo", "foo");
+        let second = ::test::commit(&repo, "bar", "bar");
+        let third = ::test::commit(&repo, "baz", "baz");
+
+        let commit = repo.find_commit(first).unwrap();
+        repo.tag_lightweight("v1.0", commit.as_object(), false).unwrap();
+
+        let oids = walk(&repo, &Revisions::new(&["v1.0..HEAD".to_string()]));
+        assert_eq!(oids, vec![third, second]);
+
+        let oids = walk(&repo, &Revisions::new(&["HEAD".to_string(), format!("^{}", second)]));
+        assert_eq!(oids, vec![third]);
+
+        let oids = walk(&repo, Revisions::new(&[]).tags(true));
+        assert_eq!(oids.first(), Some(&first));
+    }
+}
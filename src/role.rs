@@ -0,0 +1,53 @@
+use git2;
+use chrono::DateTime;
+use chrono::offset::{FixedOffset, Utc, TimeZone};
+
+/// Whose date and identity represent a commit in the reports.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    /// Who wrote the change and when, kept by rebases and cherry-picks.
+    Author,
+    /// Who created the commit and when, i.e. when the change landed.
+    Committer,
+}
+
+impl Role {
+    pub fn signature<'a>(&self, commit: &'a git2::Commit) -> git2::Signature<'a> {
+        match *self {
+            Role::Author => commit.author(),
+            Role::Committer => commit.committer(),
+        }
+    }
+
+    pub fn when(&self, commit: &git2::Commit) -> git2::Time {
+        self.signature(commit).when()
+    }
+
+    /// Date of the commit in the offset it was recorded with.
+    pub fn datetime(&self, commit: &git2::Commit) -> DateTime<FixedOffset> {
+        let time = self.when(commit);
+        let tz = FixedOffset::east_opt(time.offset_minutes() * 60).unwrap();
+        Utc.timestamp_opt(time.seconds(), 0).unwrap().with_timezone(&tz)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use git2;
+    use role::Role;
+
+    #[test]
+    fn smoke() {
+        let (_td, repo) = ::test::repo_init();
+        let tree = repo.find_tree(repo.index().unwrap().write_tree().unwrap()).unwrap();
+        let author = git2::Signature::new("author", "author@example.com", &git2::Time::new(1435475840, 6*60)).unwrap();
+        let committer = git2::Signature::new("committer", "committer@example.com", &git2::Time::new(1435562240, 0)).unwrap();
+        let id = repo.commit(None, &author, &committer, "rebased", &tree, &[]).unwrap();
+        let commit = repo.find_commit(id).unwrap();
+
+        assert_eq!(Role::Author.signature(&commit).name(), Some("author"));
+        assert_eq!(Role::Committer.signature(&commit).name(), Some("committer"));
+        assert_eq!(Role::Author.datetime(&commit).to_rfc3339(), "2015-06-28T13:17:20+06:00");
+        assert_eq!(Role::Committer.datetime(&commit).to_rfc3339(), "2015-06-29T07:17:20+00:00");
+    }
+}
@@ -1,20 +1,41 @@
 use git2;
-use std::{path,slice};
-use chrono::offset::{FixedOffset, Utc, TimeZone};
+use std::path::{self, Path};
+use std::collections::{btree_map, BTreeMap};
+use chrono::offset::FixedOffset;
 use chrono::DateTime;
+use pathspec::Pathspec;
+use filetype::{self, Breakdown};
+use loc::LineCounter;
+use role::Role;
 
+/// Name used in paths for directories whose name isn't UTF-8.
+const NON_UTF8: &'static str = "<non-utf8 string>";
+
+/// Files of the tree of a commit along with their blobs.
 pub struct Snapshot {
-    files: Vec<path::PathBuf>,
+    files: BTreeMap<path::PathBuf, git2::Oid>,
+    /// Number of files of each type.
+    types: Breakdown,
+    /// Lines of each file type, `None` until they are counted.
+    lines: Option<Breakdown>,
+    pub id: git2::Oid,
+    /// Date of the commit, author's or committer's one depending on the role.
     pub datetime: DateTime<FixedOffset>,
 }
 
 pub trait HasSnapshot {
-    fn snapshot(&self, commit: &git2::Commit, no_binary: bool) -> Result<Snapshot, git2::Error>;
+    fn snapshot(&self, commit: &git2::Commit, no_binary: bool, pathspec: Option<&Pathspec>, role: Role) -> Result<Snapshot, git2::Error>;
+
+    /// Moves the snapshot (taken without `no_binary`) to another commit by applying the diff
+    /// between their trees, so the cost depends on the size of the change rather than of the tree.
+    /// Types and lines of the files are updated from the changed files as well, the lines by `counter`.
+    fn advance(&self, snapshot: &mut Snapshot, commit: &git2::Commit, pathspec: Option<&Pathspec>, role: Role,
+               counter: &mut LineCounter) -> Result<(), git2::Error>;
 }
 
 impl HasSnapshot for git2::Repository {
-    fn snapshot(&self, commit: &git2::Commit, no_binary: bool) -> Result<Snapshot, git2::Error> {
-        let mut files: Vec<path::PathBuf> = Vec::new();
+    fn snapshot(&self, commit: &git2::Commit, no_binary: bool, pathspec: Option<&Pathspec>, role: Role) -> Result<Snapshot, git2::Error> {
+        let mut files = BTreeMap::new();
 
         let head = commit.tree()?.into_object();
         let mut trees = vec![(path::PathBuf::new(), head)];
@@ -25,7 +46,7 @@ impl HasSnapshot for git2::Repository {
                 match entry.kind() {
                     // other trees with resolved path will be added to the stack
                     Some(git2::ObjectType::Tree) => {
-                        let name = entry.name().unwrap_or("<non-utf8 string>");
+                        let name = entry.name().unwrap_or(NON_UTF8);
                         let object = entry.to_object(self)?;
                         trees.push((path.join(name), object));
                     },
@@ -34,6 +55,10 @@ impl HasSnapshot for git2::Repository {
                         if let Some(name) = entry.name() {
                             let path = path.join(name);
 
+                            if !pathspec.map_or(true, |pathspec| pathspec.matches(&path)) {
+                                continue;
+                            }
+
                             let is_binary = if no_binary {
                                 let object = entry.to_object(self)?;
                                 object.as_blob().unwrap().is_binary()
@@ -42,7 +67,7 @@ impl HasSnapshot for git2::Repository {
                             };
 
                             if !is_binary {
-                                files.push(path)
+                                files.insert(path, entry.id());
                             }
 
                         }
@@ -52,15 +77,66 @@ impl HasSnapshot for git2::Repository {
             }
         }
 
-        let time = commit.author().when();
-        let tz = FixedOffset::east(time.offset_minutes() * 60);
-        let datetime = Utc.timestamp(time.seconds(), 0).with_timezone(&tz);
-
         Ok(Snapshot {
+            types: filetype::breakdown(files.keys().map(|path| path.as_path())),
+            lines: None,
             files: files,
-            datetime: datetime,
+            id: commit.id(),
+            datetime: role.datetime(commit),
         })
     }
+
+    fn advance(&self, snapshot: &mut Snapshot, commit: &git2::Commit, pathspec: Option<&Pathspec>, role: Role,
+               counter: &mut LineCounter) -> Result<(), git2::Error> {
+        let old = self.find_commit(snapshot.id)?.tree()?;
+        let new = commit.tree()?;
+
+        let mut opts = pathspec.map(|pathspec| pathspec.diff_options());
+        let diff = self.diff_tree_to_tree(Some(&old), Some(&new), opts.as_mut())?;
+
+        for delta in diff.deltas() {
+            if delta.status() != git2::Delta::Added {
+                if let Some(path) = delta.old_file().path().and_then(snapshot_path) {
+                    snapshot.remove(counter, &path)?;
+                }
+            }
+            if delta.status() == git2::Delta::Deleted {
+                continue;
+            }
+
+            let raw = match delta.new_file().path() {
+                Some(raw) => raw,
+                None => continue,
+            };
+            // the same files as `snapshot` takes: blobs, but not submodules, with UTF-8 names
+            if new.get_path(raw)?.kind() != Some(git2::ObjectType::Blob) {
+                continue;
+            }
+            if let Some(path) = snapshot_path(raw) {
+                if pathspec.map_or(true, |pathspec| pathspec.matches(&path)) {
+                    snapshot.insert(counter, path, delta.new_file().id())?;
+                }
+            }
+        }
+
+        snapshot.id = commit.id();
+        snapshot.datetime = role.datetime(commit);
+        Ok(())
+    }
+}
+
+/// Path of the file as `snapshot` names it, `None` if the file name isn't UTF-8.
+fn snapshot_path(raw: &Path) -> Option<path::PathBuf> {
+    let mut path = path::PathBuf::new();
+    let mut components = raw.components().peekable();
+    while let Some(component) = components.next() {
+        match component.as_os_str().to_str() {
+            Some(name) => path.push(name),
+            None if components.peek().is_some() => path.push(NON_UTF8),
+            None => return None,
+        }
+    }
+    Some(path)
 }
 
 impl Snapshot {
@@ -68,18 +144,79 @@ impl Snapshot {
         self.files.len()
     }
 
-    #[allow(dead_code)]
-    pub fn iter(&self) -> slice::Iter<path::PathBuf> {
+    /// Number of files of each type.
+    pub fn types(&self) -> Breakdown {
+        self.types.clone()
+    }
+
+    /// Counts lines of the files, from then on `advance` keeps them up to date.
+    pub fn count_lines(&mut self, counter: &mut LineCounter) -> Result<(), git2::Error> {
+        let lines = counter.count(self)?;
+        self.lines = Some(lines);
+        Ok(())
+    }
+
+    /// Lines of each file type, `None` unless `count_lines` was called.
+    pub fn lines(&self) -> Option<&Breakdown> {
+        self.lines.as_ref()
+    }
+
+    fn insert(&mut self, counter: &mut LineCounter, path: path::PathBuf, id: git2::Oid) -> Result<(), git2::Error> {
+        let kind = filetype::classify(&path);
+        *self.types.entry(kind).or_insert(0) += 1;
+        if let Some(ref mut lines) = self.lines {
+            *lines.entry(kind).or_insert(0) += counter.lines(id)?;
+        }
+        self.files.insert(path, id);
+        Ok(())
+    }
+
+    fn remove(&mut self, counter: &mut LineCounter, path: &Path) -> Result<(), git2::Error> {
+        let id = match self.files.remove(path) {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let kind = filetype::classify(path);
+        let left = match self.types.get_mut(kind) {
+            Some(num) => { *num -= 1; *num },
+            None => 0,
+        };
+        // types without files are left out, as in a snapshot taken anew
+        if left == 0 {
+            self.types.remove(kind);
+        }
+        if let Some(ref mut lines) = self.lines {
+            if left == 0 {
+                lines.remove(kind);
+            } else if let Some(num) = lines.get_mut(kind) {
+                *num -= counter.lines(id)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Paths of the files, sorted.
+    pub fn iter(&self) -> btree_map::Keys<path::PathBuf, git2::Oid> {
+        self.files.keys()
+    }
+
+    /// Pairs of the file path and its blob.
+    pub fn blobs(&self) -> btree_map::Iter<path::PathBuf, git2::Oid> {
         self.files.iter()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::ffi::OsStr;
     use std::fs::{self, File};
     use std::io::prelude::*;
+    use std::os::unix::ffi::OsStrExt;
     use std::path::{Path,PathBuf};
     use snapshot::HasSnapshot;
+    use loc::LineCounter;
+    use role::Role;
 
     #[test]
     fn smoke() {
@@ -101,8 +238,63 @@ mod tests {
                              &tree, &[&parent]).unwrap();
         let commit = repo.find_commit(id).unwrap();
 
-        let files = repo.snapshot(&commit, false).unwrap();
+        let files = repo.snapshot(&commit, false, None, Role::Author).unwrap();
         assert_eq!(files.len(), 1);
         assert_eq!(files.iter().next(), Some(&PathBuf::from("foo/bar")));
     }
+
+    #[test]
+    fn advance() {
+        let (_td, repo) = ::test::repo_init();
+        ::test::commit(&repo, "a", "a");
+        let id = ::test::commit(&repo, "dir/b", "b");
+        let mut files = repo.snapshot(&repo.find_commit(id).unwrap(), false, None, Role::Author).unwrap();
+        let mut counter = LineCounter::new(&repo);
+        files.count_lines(&mut counter).unwrap();
+
+        ::test::commit(&repo, "dir/b", "changed");
+        ::test::commit(&repo, "c", "c");
+        let mut index = repo.index().unwrap();
+        index.remove_path(Path::new("a")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let sig = repo.signature().unwrap();
+        let parent = repo.find_commit(repo.refname_to_id("HEAD").unwrap()).unwrap();
+        let id = repo.commit(Some("HEAD"), &sig, &sig, "remove", &tree, &[&parent]).unwrap();
+
+        let commit = repo.find_commit(id).unwrap();
+        repo.advance(&mut files, &commit, None, Role::Author, &mut counter).unwrap();
+        let mut expected = repo.snapshot(&commit, false, None, Role::Author).unwrap();
+        expected.count_lines(&mut counter).unwrap();
+
+        assert_eq!(files.id, id);
+        assert_eq!(files.blobs().collect::<Vec<_>>(), expected.blobs().collect::<Vec<_>>());
+        assert_eq!(files.types(), expected.types());
+        assert_eq!(files.lines(), expected.lines());
+        assert_eq!(files.iter().collect::<Vec<_>>(), vec![&PathBuf::from("c"), &PathBuf::from("dir/b")]);
+
+        // a submodule, a file with a non-UTF-8 name and a file in a directory with such a name
+        let blob = repo.blob(b"fn main() {}\n").unwrap();
+        let mut dir = repo.treebuilder(None).unwrap();
+        dir.insert("x.rs", blob, 0o100644).unwrap();
+        let dir = dir.write().unwrap();
+        let mut builder = repo.treebuilder(Some(&tree)).unwrap();
+        builder.insert("sub", parent.id(), 0o160000).unwrap();
+        builder.insert(Path::new(OsStr::from_bytes(b"caf\xe9.rs")), blob, 0o100644).unwrap();
+        builder.insert(Path::new(OsStr::from_bytes(b"d\xe9")), dir, 0o040000).unwrap();
+        let tree = repo.find_tree(builder.write().unwrap()).unwrap();
+        let parent = repo.find_commit(id).unwrap();
+        let id = repo.commit(Some("HEAD"), &sig, &sig, "non-blobs", &tree, &[&parent]).unwrap();
+
+        let commit = repo.find_commit(id).unwrap();
+        repo.advance(&mut files, &commit, None, Role::Author, &mut counter).unwrap();
+        let mut expected = repo.snapshot(&commit, false, None, Role::Author).unwrap();
+        expected.count_lines(&mut counter).unwrap();
+
+        assert_eq!(files.blobs().collect::<Vec<_>>(), expected.blobs().collect::<Vec<_>>());
+        assert_eq!(files.types(), expected.types());
+        assert_eq!(files.lines(), expected.lines());
+        assert_eq!(files.lines().unwrap().get("Rust"), Some(&1));
+        assert_eq!(files.iter().collect::<Vec<_>>(),
+                   vec![&PathBuf::from("<non-utf8 string>/x.rs"), &PathBuf::from("c"), &PathBuf::from("dir/b")]);
+    }
 }
@@ -0,0 +1,157 @@
+use std::{cmp, env};
+use std::str::FromStr;
+use atty;
+
+/// Whether the text report is coloured, as given by `--color`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorChoice {
+    /// Colours when stdout is a terminal and `NO_COLOR` isn't set.
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<ColorChoice, String> {
+        match input {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            _ => Err(format!("invalid color: {}, expected auto, always or never", input)),
+        }
+    }
+}
+
+/// Palette of the terminal.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Colors {
+    None,
+    Ansi256,
+    TrueColor,
+}
+
+/// How charts and tables of the text report are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub colors: Colors,
+    /// Only ASCII characters, for terminals without the block and box-drawing glyphs.
+    pub ascii: bool,
+}
+
+/// Gradient of the intensity levels from none to the max, as in `Heatmap`.
+const ANSI256: [u8; 5] = [240, 22, 28, 34, 46];
+const TRUECOLOR: [(u8, u8, u8); 5] = [(110, 118, 129), (14, 68, 41), (0, 109, 50), (38, 166, 65), (57, 211, 83)];
+
+impl Style {
+    /// Monochrome with Unicode glyphs.
+    pub fn plain() -> Style {
+        Style { colors: Colors::None, ascii: false }
+    }
+
+    /// Looks at stdout, `NO_COLOR`, `TERM`, `COLORTERM` and the locale.
+    pub fn detect(choice: ColorChoice) -> Style {
+        let term = env::var("TERM").unwrap_or_default();
+
+        let colored = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => atty::is(atty::Stream::Stdout) && env::var_os("NO_COLOR").is_none() && term != "dumb",
+        };
+        let colors = if !colored {
+            Colors::None
+        } else {
+            match env::var("COLORTERM") {
+                Ok(ref value) if value == "truecolor" || value == "24bit" => Colors::TrueColor,
+                _ => Colors::Ansi256,
+            }
+        };
+
+        // the first of the locale variables which is set defines the charset
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"].iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|value| !value.is_empty());
+        let utf8 = locale.map_or(true, |locale| {
+            let locale = locale.to_lowercase();
+            locale.contains("utf-8") || locale.contains("utf8")
+        });
+
+        Style { colors: colors, ascii: !utf8 || term == "linux" || term == "dumb" }
+    }
+
+    /// Paints the text with the colour of the level, 0 is the colour of nothing, 4 is of the max.
+    pub fn paint(&self, text: &str, level: usize) -> String {
+        let level = cmp::min(4, level);
+        match self.colors {
+            Colors::None => text.to_string(),
+            Colors::Ansi256 => format!("\x1b[38;5;{}m{}\x1b[0m", ANSI256[level], text),
+            Colors::TrueColor => {
+                let (r, g, b) = TRUECOLOR[level];
+                format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, text)
+            }
+        }
+    }
+
+    /// Level of the value, only zero gets the level 0.
+    pub fn level(value: usize, max: usize) -> usize {
+        if value == 0 || max == 0 {
+            0
+        } else {
+            cmp::min(4, 1 + value * 3 / max)
+        }
+    }
+
+    /// Glyphs of the intensity levels of heatmap cells.
+    pub fn cells(&self) -> [&'static str; 5] {
+        if self.ascii { [".", "-", "+", "*", "#"] } else { [".", "▪", "◾", "◼", "⬛"] }
+    }
+
+    /// Narrow glyphs of the intensity levels, one column wide.
+    pub fn shades(&self) -> [&'static str; 5] {
+        if self.ascii { [".", ":", "+", "*", "#"] } else { [".", "░", "▒", "▓", "█"] }
+    }
+
+    /// Glyph of the bar body and of its end.
+    pub fn bar(&self) -> (&'static str, &'static str) {
+        if self.ascii { ("#", "|") } else { ("░", "▏") }
+    }
+
+    /// Bar of the given length, painted by its share of the max.
+    pub fn paint_bar(&self, length: usize, value: usize, max: usize) -> String {
+        let (body, end) = self.bar();
+        let bar: String = (0..length).map(|_| body).collect();
+        self.paint(&bar, Style::level(value, max)) + end
+    }
+}
+
+impl Default for Style {
+    fn default() -> Style {
+        Style::plain()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use style::{Colors, Style};
+
+    #[test]
+    fn smoke() {
+        let plain = Style::plain();
+        assert_eq!(plain.paint("x", 4), "x");
+        assert_eq!(plain.paint_bar(3, 1, 2), "░░░▏");
+
+        let ascii = Style { colors: Colors::None, ascii: true };
+        assert_eq!(ascii.paint_bar(3, 1, 2), "###|");
+
+        let ansi = Style { colors: Colors::Ansi256, ascii: false };
+        assert_eq!(ansi.paint("x", 4), "\x1b[38;5;46mx\x1b[0m");
+        let truecolor = Style { colors: Colors::TrueColor, ascii: false };
+        assert_eq!(truecolor.paint("x", 0), "\x1b[38;2;110;118;129mx\x1b[0m");
+
+        assert_eq!(Style::level(0, 10), 0);
+        assert_eq!(Style::level(1, 10), 1);
+        assert_eq!(Style::level(5, 10), 2);
+        assert_eq!(Style::level(10, 10), 4);
+    }
+}
@@ -0,0 +1,474 @@
+use std::fmt;
+use std::collections::{BTreeSet, HashMap};
+use git2;
+
+use error::Error;
+use mailmap::{Identity, Mailmap};
+use revision::Revisions;
+
+/// Names and local-parts of emails shared by unrelated people, they never link identities.
+const GENERIC: [&'static str; 24] = [
+    "admin", "administrator", "bot", "build", "ci", "dev", "developer", "git", "github", "gitlab",
+    "info", "mail", "me", "none", "noreply", "no reply", "root", "test", "ubuntu", "unknown", "user",
+    "you", "your name", "yourname",
+];
+
+/// Why identities are considered to be the same person.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Reason {
+    /// Names are equal up to case, punctuation and the order of words.
+    SameName,
+    /// Emails are equal up to case, names differ.
+    SameEmail,
+    /// Local-parts are equal and so are domains, e.g. `jdoe@mail.example.com` and `jdoe@example.com`.
+    SharedDomain,
+    /// Distinctive local-parts are equal at different domains.
+    SameLocalPart,
+    /// One committed the other's commits and the name of one matches the other's email.
+    CoOccurrence,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Reason::SameName => "same name",
+            Reason::SameEmail => "same email",
+            Reason::SharedDomain => "same local-part at the same domain",
+            Reason::SameLocalPart => "same local-part",
+            Reason::CoOccurrence => "committed each other's commits",
+        })
+    }
+}
+
+/// Identities which are likely the same person, with the mailmap lines merging them.
+#[derive(Debug)]
+pub struct Suggestion {
+    /// Identity the others are mapped to, the one with the most commits.
+    pub canonical: Identity,
+    /// Identities of commits, as they are, which are mapped to the canonical one by `lines`.
+    pub aliases: Vec<Identity>,
+    pub reasons: BTreeSet<Reason>,
+    /// Proposed mailmap lines, without line breaks.
+    pub lines: Vec<Vec<u8>>,
+}
+
+/// Identity after mailmap with the identities of commits mapped to it.
+struct Node {
+    identity: Identity,
+    raw: BTreeSet<Identity>,
+    /// Commits where it is the author or the committer.
+    commits: usize,
+}
+
+/// Scans authors and committers of the commits and clusters identities which are likely the same person.
+/// Identities already merged by the mailmap are treated as one, so only new lines are proposed.
+pub fn suggest(repo: &git2::Repository, revisions: &Revisions, mailmap: Option<&Mailmap>)
+               -> Result<Vec<Suggestion>, Error> {
+    let mut revwalk = repo.revwalk()?;
+    revisions.push(repo, &mut revwalk)?;
+
+    let mut nodes: Vec<Node> = Vec::new();
+    let mut index: HashMap<Identity, usize> = HashMap::new();
+    // authors and committers of the same commits
+    let mut pairs: BTreeSet<(usize, usize)> = BTreeSet::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        let author = add_node(&mut nodes, &mut index, Identity::new(&commit.author()), mailmap);
+        let committer = add_node(&mut nodes, &mut index, Identity::new(&commit.committer()), mailmap);
+        nodes[author].commits += 1;
+        if author != committer {
+            nodes[committer].commits += 1;
+            pairs.insert((author, committer));
+        }
+    }
+
+    let mut clusters = Clusters::new(nodes.len());
+
+    link_by(&nodes, &mut clusters, Reason::SameName, |node| name_key(&node.identity.name), |_, _| true);
+    link_by(&nodes, &mut clusters, Reason::SameEmail, |node| {
+        let (local, domain) = split_email(&node.identity.email);
+        if is_generic(&local) || !domain.contains('.') { None } else { Some(format!("{}@{}", local, domain)) }
+    }, |_, _| true);
+    link_by(&nodes, &mut clusters, Reason::SharedDomain, |node| {
+        let (local, domain) = split_email(&node.identity.email);
+        let local = local_key(&local, &domain);
+        if is_generic(&local) || local.is_empty() || !domain.contains('.') { None } else { Some(format!("{}@{}", local, domain_root(&domain))) }
+    }, |a, b| lowercase(&a.identity.email) != lowercase(&b.identity.email));
+    link_by(&nodes, &mut clusters, Reason::SameLocalPart, |node| {
+        let (local, domain) = split_email(&node.identity.email);
+        let local = local_key(&local, &domain);
+        if is_generic(&local) || local.len() < 4 || local.chars().all(|c| c.is_digit(10)) { None } else { Some(local) }
+    }, |a, b| {
+        domain_root(&split_email(&a.identity.email).1) != domain_root(&split_email(&b.identity.email).1)
+    });
+    for &(author, committer) in &pairs {
+        if matches(&nodes[author].identity, &nodes[committer].identity) {
+            clusters.link(author, committer, Reason::CoOccurrence);
+        }
+    }
+
+    // emails of commits whose identities are all unmapped, one line per email is enough for them
+    let mut by_email: HashMap<Vec<u8>, Vec<(usize, &Identity)>> = HashMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        for raw in &node.raw {
+            by_email.entry(raw.email.to_ascii_lowercase()).or_insert_with(Vec::new).push((i, raw));
+        }
+    }
+
+    let mut suggestions = Vec::new();
+    for (_, mut members) in clusters.groups() {
+        if members.len() < 2 {
+            continue;
+        }
+        members.sort_by(|&a, &b| {
+            let (a, b) = (&nodes[a], &nodes[b]);
+            b.commits.cmp(&a.commits)
+                .then_with(|| b.identity.name.contains(&b' ').cmp(&a.identity.name.contains(&b' ')))
+                .then_with(|| a.identity.cmp(&b.identity))
+        });
+        let canonical = nodes[members[0]].identity.clone();
+        let root = clusters.find(members[0]);
+
+        let mut aliases = Vec::new();
+        let mut lines: Vec<Vec<u8>> = Vec::new();
+        for &i in &members[1..] {
+            for raw in &nodes[i].raw {
+                aliases.push(raw.clone());
+
+                let owners = &by_email[&raw.email.to_ascii_lowercase()];
+                let whole_email = owners.iter().all(|&(j, other)| {
+                    clusters.find(j) == root && mailmap.map_or(true, |m| m.lookup(&other.name, &other.email).is_none())
+                });
+                let mut line = canonical.to_bytes();
+                if !whole_email {
+                    // `Proper Name <proper@email> Commit Name <commit@email>`
+                    line.push(b' ');
+                    line.extend_from_slice(&raw.to_bytes());
+                } else if raw.email != canonical.email {
+                    // `Proper Name <proper@email> <commit@email>`
+                    line.extend_from_slice(b" <");
+                    line.extend_from_slice(&raw.email);
+                    line.push(b'>');
+                } else if canonical.name.is_empty() {
+                    // `<email>` alone changes nothing
+                    continue;
+                }
+                if !lines.contains(&line) {
+                    lines.push(line);
+                }
+            }
+        }
+
+        suggestions.push(Suggestion {
+            canonical: canonical,
+            aliases: aliases,
+            reasons: clusters.reasons(root),
+            lines: lines,
+        });
+    }
+    suggestions.sort_by(|a, b| a.canonical.cmp(&b.canonical));
+
+    Ok(suggestions)
+}
+
+/// Mailmap lines of the suggestions, each cluster is preceded by a comment with the reasons.
+pub fn render(suggestions: &[Suggestion]) -> Vec<u8> {
+    let mut result = Vec::new();
+    for (i, suggestion) in suggestions.iter().enumerate() {
+        if i > 0 {
+            result.push(b'\n');
+        }
+        let reasons: Vec<String> = suggestion.reasons.iter().map(|reason| reason.to_string()).collect();
+        result.extend_from_slice(b"# ");
+        result.extend_from_slice(&suggestion.canonical.to_bytes());
+        result.extend_from_slice(format!(": {}\n", reasons.join(", ")).as_bytes());
+        for line in &suggestion.lines {
+            result.extend_from_slice(line);
+            result.push(b'\n');
+        }
+    }
+    result
+}
+
+/// Unified diff appending `added` to the mailmap, `None` if there is no mailmap yet.
+pub fn diff(existing: Option<&[u8]>, added: &[u8]) -> Vec<u8> {
+    let added: Vec<&[u8]> = added.split(|&byte| byte == b'\n').collect();
+    // split leaves an empty piece after the last line break
+    let added = &added[..added.len() - 1];
+
+    let mut result = Vec::new();
+    let existing = match existing {
+        Some(existing) => existing,
+        None => {
+            result.extend_from_slice(format!("--- /dev/null\n+++ b/.mailmap\n@@ -0,0 +1,{} @@\n", added.len()).as_bytes());
+            for line in added {
+                result.push(b'+');
+                result.extend_from_slice(line);
+                result.push(b'\n');
+            }
+            return result;
+        }
+    };
+
+    let mut lines: Vec<&[u8]> = existing.split(|&byte| byte == b'\n').collect();
+    let last = lines.pop().unwrap_or(b"");
+    result.extend_from_slice(b"--- a/.mailmap\n+++ b/.mailmap\n");
+    if last.is_empty() {
+        // the last line is the context, git apply doesn't take hunks without one
+        match lines.last() {
+            Some(context) => {
+                result.extend_from_slice(format!("@@ -{},1 +{},{} @@\n ", lines.len(), lines.len(), added.len() + 1).as_bytes());
+                result.extend_from_slice(context);
+                result.push(b'\n');
+            },
+            None => result.extend_from_slice(format!("@@ -0,0 +1,{} @@\n", added.len()).as_bytes()),
+        }
+    } else {
+        // the last line isn't terminated, it's replaced by the terminated one
+        let number = lines.len() + 1;
+        result.extend_from_slice(format!("@@ -{},1 +{},{} @@\n-", number, number, added.len() + 1).as_bytes());
+        result.extend_from_slice(last);
+        result.extend_from_slice(b"\n\\ No newline at end of file\n+");
+        result.extend_from_slice(last);
+        result.push(b'\n');
+    }
+    for line in added {
+        result.push(b'+');
+        result.extend_from_slice(line);
+        result.push(b'\n');
+    }
+    result
+}
+
+/// Disjoint sets of identities with the reasons of each link.
+struct Clusters {
+    parents: Vec<usize>,
+    reasons: Vec<(usize, Reason)>,
+}
+
+impl Clusters {
+    fn new(len: usize) -> Clusters {
+        Clusters { parents: (0..len).collect(), reasons: Vec::new() }
+    }
+
+    fn find(&self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn link(&mut self, a: usize, b: usize, reason: Reason) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[b] = a;
+        self.reasons.push((a, reason));
+    }
+
+    fn reasons(&self, root: usize) -> BTreeSet<Reason> {
+        self.reasons.iter().filter(|&&(i, _)| self.find(i) == root).map(|&(_, reason)| reason).collect()
+    }
+
+    /// Members of each cluster, keyed by its root.
+    fn groups(&self) -> HashMap<usize, Vec<usize>> {
+        let mut result: HashMap<usize, Vec<usize>> = HashMap::new();
+        for i in 0..self.parents.len() {
+            result.entry(self.find(i)).or_insert_with(Vec::new).push(i);
+        }
+        result
+    }
+}
+
+/// Index of the node of the identity after mailmap, it's added if there is none yet.
+fn add_node(nodes: &mut Vec<Node>, index: &mut HashMap<Identity, usize>, raw: Identity, mailmap: Option<&Mailmap>) -> usize {
+    let identity = mailmap.map_or_else(|| raw.clone(), |mailmap| mailmap.map(&raw));
+    let i = match index.get(&identity) {
+        Some(&i) => i,
+        None => {
+            nodes.push(Node { identity: identity.clone(), raw: BTreeSet::new(), commits: 0 });
+            index.insert(identity, nodes.len() - 1);
+            nodes.len() - 1
+        }
+    };
+    nodes[i].raw.insert(raw);
+    i
+}
+
+/// Links the nodes with the same key, if the pair is related.
+fn link_by<K, R>(nodes: &[Node], clusters: &mut Clusters, reason: Reason, key: K, related: R)
+    where K: Fn(&Node) -> Option<String>, R: Fn(&Node, &Node) -> bool {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if let Some(key) = key(node) {
+            groups.entry(key).or_insert_with(Vec::new).push(i);
+        }
+    }
+
+    for (_, members) in groups {
+        for (k, &i) in members.iter().enumerate() {
+            if let Some(&j) = members[..k].iter().find(|&&j| related(&nodes[j], &nodes[i])) {
+                clusters.link(j, i, reason);
+            }
+        }
+    }
+}
+
+fn lowercase(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).to_lowercase()
+}
+
+/// Lowercased words of the name.
+fn words(name: &[u8]) -> Vec<String> {
+    lowercase(name).split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_string())
+        .collect()
+}
+
+fn is_generic(text: &str) -> bool {
+    GENERIC.contains(&text)
+}
+
+/// Words of the name in order, so `Doe, John` and `john doe` are the same.
+fn name_key(name: &[u8]) -> Option<String> {
+    let words = words(name);
+    if words.is_empty() || is_generic(&words.join(" ")) {
+        return None;
+    }
+    let mut sorted = words;
+    sorted.sort();
+    Some(sorted.join(" "))
+}
+
+/// Lowercased local-part and domain.
+fn split_email(email: &[u8]) -> (String, String) {
+    let email = lowercase(email);
+    match email.rfind('@') {
+        Some(at) => (email[..at].to_string(), email[at + 1..].to_string()),
+        None => (email, String::new()),
+    }
+}
+
+/// Local-part without `+tags` and punctuation, for GitHub's noreply addresses it's the login.
+fn local_key(local: &str, domain: &str) -> String {
+    let local = match local.find('+') {
+        Some(plus) if domain == "users.noreply.github.com" => &local[plus + 1..],
+        Some(plus) => &local[..plus],
+        None => local,
+    };
+    local.chars().filter(|&c| c != '.' && c != '_' && c != '-').collect()
+}
+
+/// The last two labels of the domain, e.g. `example.com` for `mail.example.com`.
+fn domain_root(domain: &str) -> String {
+    let labels: Vec<&str> = domain.split('.').collect();
+    let start = if labels.len() > 2 { labels.len() - 2 } else { 0 };
+    labels[start..].join(".")
+}
+
+/// Whether the name of one identity matches the other's: the words of one are among the other's,
+/// or the local-part of one's email is a handle made of the other's name, like `jdoe` or `johndoe`.
+fn matches(a: &Identity, b: &Identity) -> bool {
+    let (a_words, b_words) = (words(&a.name), words(&b.name));
+    let subset = |x: &Vec<String>, y: &Vec<String>| !x.is_empty() && x.iter().all(|word| y.contains(word));
+    if subset(&a_words, &b_words) || subset(&b_words, &a_words) {
+        return true;
+    }
+
+    let handle_of = |words: &Vec<String>, email: &[u8]| {
+        let (local, domain) = split_email(email);
+        let local = local_key(&local, &domain);
+        if is_generic(&local) || local.len() < 3 {
+            return false;
+        }
+        match (words.first(), words.last()) {
+            (Some(first), Some(last)) if words.len() > 1 => {
+                let initial: String = first.chars().take(1).collect();
+                local == words.concat() || local == initial + last || local == format!("{}{}", last, first)
+            },
+            (Some(single), _) => local == *single,
+            _ => false,
+        }
+    };
+    handle_of(&a_words, &b.email) || handle_of(&b_words, &a.email)
+}
+
+#[cfg(test)]
+mod tests {
+    use git2;
+    use mailmap::Mailmap;
+    use revision::Revisions;
+    use suggest::{diff, render, suggest, Reason};
+
+    /// Commits on top of HEAD with the given author and committer.
+    fn commit(repo: &git2::Repository, author: (&str, &str), committer: (&str, &str)) {
+        let head = repo.find_commit(repo.refname_to_id("HEAD").unwrap()).unwrap();
+        let time = git2::Time::new(1435475840, 0);
+        let author = git2::Signature::new(author.0, author.1, &time).unwrap();
+        let committer = git2::Signature::new(committer.0, committer.1, &time).unwrap();
+        repo.commit(Some("HEAD"), &author, &committer, "commit", &head.tree().unwrap(), &[&head]).unwrap();
+    }
+
+    #[test]
+    fn smoke() {
+        let (_td, repo) = ::test::repo_init();
+        let alice = ("Alice Smith", "alice@example.com");
+        commit(&repo, alice, alice);
+        commit(&repo, alice, alice);
+        commit(&repo, ("smith, alice", "asmith@old.example.org"), ("smith, alice", "asmith@old.example.org"));
+        commit(&repo, ("Bob Jones", "bob@example.com"), ("Bob Jones", "bob@example.com"));
+        commit(&repo, ("bob", "bob@mail.example.com"), ("bob", "bob@mail.example.com"));
+        commit(&repo, ("Dave Brown", "dave@x.com"), ("dbrown", "dbrown@y.com"));
+        // unrelated people behind generic identities aren't merged
+        commit(&repo, ("Eve", "root@localhost"), ("Mallory", "root@localhost"));
+
+        let suggestions = suggest(&repo, &Revisions::new(&[]), None).unwrap();
+        let canonicals: Vec<String> = suggestions.iter().map(|s| s.canonical.to_string()).collect();
+        assert_eq!(canonicals, vec!["Alice Smith <alice@example.com>", "Bob Jones <bob@example.com>",
+                                    "Dave Brown <dave@x.com>"]);
+        assert!(suggestions[0].reasons.contains(&Reason::SameName));
+        assert!(suggestions[1].reasons.contains(&Reason::SharedDomain));
+        assert!(suggestions[2].reasons.contains(&Reason::CoOccurrence));
+
+        let text = String::from_utf8(render(&suggestions)).unwrap();
+        assert!(text.starts_with("# Alice Smith <alice@example.com>: same name\n\
+                                  Alice Smith <alice@example.com> <asmith@old.example.org>\n\n"));
+
+        // the lines merge the identities
+        let mut mailmap = Mailmap::empty();
+        mailmap.read(&render(&suggestions)[..]).unwrap();
+        assert!(suggest(&repo, &Revisions::new(&[]), Some(&mailmap)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn shared_email() {
+        let (_td, repo) = ::test::repo_init();
+        let eve = ("Eve", "eve@example.com");
+        commit(&repo, eve, eve);
+        commit(&repo, ("Eve", "root@localhost"), eve);
+        commit(&repo, ("Eve E", "root@localhost"), eve);
+        commit(&repo, ("Mallory", "root@localhost"), ("Mallory", "root@localhost"));
+
+        let mut mailmap = Mailmap::empty();
+        mailmap.read(&b"Eve <eve@example.com> Eve <root@localhost>\n"[..]).unwrap();
+        let suggestions = suggest(&repo, &Revisions::new(&[]), Some(&mailmap)).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].reasons.iter().cloned().collect::<Vec<_>>(), vec![Reason::CoOccurrence]);
+        // the email is shared with Mallory, so only the name is mapped
+        assert_eq!(suggestions[0].lines, vec![b"Eve <eve@example.com> Eve E <root@localhost>".to_vec()]);
+    }
+
+    #[test]
+    fn unified_diff() {
+        let added = b"# A <a@example.com>: same name\nA <a@example.com> <b@example.com>\n";
+        assert_eq!(String::from_utf8(diff(None, added)).unwrap(),
+                   "--- /dev/null\n+++ b/.mailmap\n@@ -0,0 +1,2 @@\n\
+                    +# A <a@example.com>: same name\n+A <a@example.com> <b@example.com>\n");
+        assert_eq!(String::from_utf8(diff(Some(b"X <x@example.com>\n"), added)).unwrap(),
+                   "--- a/.mailmap\n+++ b/.mailmap\n@@ -1,1 +1,3 @@\n X <x@example.com>\n\
+                    +# A <a@example.com>: same name\n+A <a@example.com> <b@example.com>\n");
+        assert_eq!(String::from_utf8(diff(Some(b"X <x@example.com>"), added)).unwrap(),
+                   "--- a/.mailmap\n+++ b/.mailmap\n@@ -1,1 +1,3 @@\n-X <x@example.com>\n\
+                    \\ No newline at end of file\n+X <x@example.com>\n\
+                    +# A <a@example.com>: same name\n+A <a@example.com> <b@example.com>\n");
+    }
+}
@@ -2,7 +2,19 @@ use std::path::{Path, PathBuf};
 use std::io;
 use tempdir::TempDir;
 
-use git2::Repository;
+use git2::{Oid, Repository, ResetType, Signature};
+
+/// Repository without any commits, HEAD is unborn.
+pub fn repo_init_empty() -> (TempDir, Repository) {
+    let td = TempDir::new("test").unwrap();
+    let repo = Repository::init(td.path()).unwrap();
+    {
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "name").unwrap();
+        config.set_str("user.email", "email").unwrap();
+    }
+    (td, repo)
+}
 
 pub fn repo_init() -> (TempDir, Repository) {
     let td = TempDir::new("test").unwrap();
@@ -20,3 +32,51 @@ pub fn repo_init() -> (TempDir, Repository) {
     }
     (td, repo)
 }
+
+/// Writes `content` into `path` and commits it on top of HEAD.
+pub fn commit(repo: &Repository, path: &str, content: &str) -> Oid {
+    let sig = repo.signature().unwrap();
+    commit_as(repo, path, content, &sig)
+}
+
+/// Same as `commit`, authored and committed by `sig`.
+pub fn commit_as(repo: &Repository, path: &str, content: &str, sig: &Signature) -> Oid {
+    use std::fs::{self, File};
+    use std::io::Write;
+
+    let root = repo.path().parent().unwrap();
+    let file = root.join(path);
+    fs::create_dir_all(file.parent().unwrap()).unwrap();
+    File::create(&file).unwrap().write_all(content.as_bytes()).unwrap();
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(path)).unwrap();
+    let id = index.write_tree().unwrap();
+    let tree = repo.find_tree(id).unwrap();
+    let id = repo.refname_to_id("HEAD").unwrap();
+    let parent = repo.find_commit(id).unwrap();
+
+    repo.commit(Some("HEAD"), sig, sig, path, &tree, &[&parent]).unwrap()
+}
+
+/// Commits `side_path` on a side branch forked from HEAD, `main_path` on HEAD
+/// and merges the side branch into HEAD. Returns the merge commit.
+pub fn merge(repo: &Repository, side_path: &str, side_content: &str,
+             main_path: &str, main_content: &str) -> Oid {
+    let fork = repo.refname_to_id("HEAD").unwrap();
+    let side = commit(repo, side_path, side_content);
+
+    let fork = repo.find_object(fork, None).unwrap();
+    repo.reset(&fork, ResetType::Mixed, None).unwrap();
+    commit(repo, main_path, main_content);
+
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new(side_path)).unwrap();
+    let id = index.write_tree().unwrap();
+    let tree = repo.find_tree(id).unwrap();
+    let sig = repo.signature().unwrap();
+    let head = repo.find_commit(repo.refname_to_id("HEAD").unwrap()).unwrap();
+    let side = repo.find_commit(side).unwrap();
+
+    repo.commit(Some("HEAD"), &sig, &sig, "merge", &tree, &[&head, &side]).unwrap()
+}